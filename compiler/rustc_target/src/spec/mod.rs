@@ -1788,6 +1788,8 @@ fn $module() {
     ("armv7-unknown-linux-ohos", armv7_unknown_linux_ohos),
     ("x86_64-unknown-linux-ohos", x86_64_unknown_linux_ohos),
 
+    ("aarch64-unknown-emerald", aarch64_unknown_emerald),
+    ("riscv64gc-unknown-emerald", riscv64gc_unknown_emerald),
     ("x86_64-unknown-emerald", x86_64_unknown_emerald),
 }
 
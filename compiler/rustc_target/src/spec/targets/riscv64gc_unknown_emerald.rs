@@ -0,0 +1,24 @@
+use crate::spec::{base, CodeModel, Target, TargetOptions};
+
+pub fn target() -> Target {
+    Target {
+        llvm_target: "riscv64".into(),
+        metadata: crate::spec::TargetMetadata {
+            description: Some("RISC-V Emerald OS".into()),
+            tier: Some(3),
+            host_tools: Some(false),
+            std: None,
+        },
+        pointer_width: 64,
+        arch: "riscv64".into(),
+        data_layout: "e-m:e-p:64:64-i64:64-i128:128-n32:64-S128".into(),
+        options: TargetOptions {
+            cpu: "generic-rv64".into(),
+            features: "+m,+a,+f,+d,+c".into(),
+            code_model: Some(CodeModel::Medium),
+            max_atomic_width: Some(64),
+            llvm_abiname: "lp64d".into(),
+            ..base::emerald::opts()
+        },
+    }
+}
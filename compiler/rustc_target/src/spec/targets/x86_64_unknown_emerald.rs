@@ -14,10 +14,10 @@ pub fn target() -> Target {
         data_layout: "e-m:e-p270:32:32-p271:32:32-p272:64:64-i64:64-i128:128-f80:128-n8:16:32:64-S128"
             .into(),
         metadata: crate::spec::TargetMetadata {
-            description: None,
-            tier: None,
-            host_tools: None,
-            std: None,
+            description: Some("x86_64 Emerald OS".into()),
+            tier: Some(3),
+            host_tools: Some(false),
+            std: Some(true),
         },
         options: base,
     }
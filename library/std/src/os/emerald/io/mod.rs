@@ -2,9 +2,12 @@
 
 mod owned;
 mod raw;
+mod terminal;
 
 // Export the types and traits for the public API.
 #[stable(feature = "os_fd", since = "1.66.0")]
 pub use owned::*;
 #[stable(feature = "os_fd", since = "1.66.0")]
 pub use raw::*;
+#[unstable(feature = "emerald_terminal", issue = "none")]
+pub use terminal::*;
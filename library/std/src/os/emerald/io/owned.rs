@@ -175,6 +175,7 @@ unsafe fn from_raw_fd(fd: RawFd) -> Self {
 impl Drop for OwnedFd {
     #[inline]
     fn drop(&mut self) {
+        crate::sys::io::forget_raw_mode(self.fd);
         unsafe {
             emerald_std::io::syscall_close(self.fd)
                 .expect("Invalid FD somehow slipped and should not be closed")
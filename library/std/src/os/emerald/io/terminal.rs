@@ -0,0 +1,73 @@
+//! Terminal extensions for Emerald file descriptors.
+
+#![unstable(feature = "emerald_terminal", issue = "none")]
+
+use super::owned::AsFd;
+use crate::io;
+
+/// The size of a terminal, in characters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[unstable(feature = "emerald_terminal", issue = "none")]
+pub struct WindowSize {
+    /// Number of rows (lines) of the terminal.
+    pub rows: u16,
+    /// Number of columns (characters per line) of the terminal.
+    pub columns: u16,
+}
+
+/// Emerald-specific extensions for file descriptors that refer to a terminal.
+///
+/// All methods fail with [`io::ErrorKind::InvalidInput`] if the file descriptor
+/// is not a terminal, use [`io::IsTerminal`] to check first.
+#[unstable(feature = "emerald_terminal", issue = "none")]
+pub trait TerminalExt: AsFd {
+    /// Switches the terminal between raw and cooked (line buffered) mode.
+    ///
+    /// In raw mode, reads return as soon as any character is typed, instead of
+    /// waiting for a full line.
+    ///
+    /// A nonblocking terminal stays nonblocking, the new mode is used once it's
+    /// made blocking again with `set_nonblocking(false)`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// #![feature(emerald_terminal)]
+    /// use std::io::{self, Read};
+    /// use std::os::emerald::io::TerminalExt;
+    ///
+    /// fn main() -> io::Result<()> {
+    ///     let mut stdin = io::stdin();
+    ///     stdin.set_raw_mode(true)?;
+    ///
+    ///     // read one key press
+    ///     let mut key = [0u8; 1];
+    ///     stdin.read_exact(&mut key)?;
+    ///
+    ///     stdin.set_raw_mode(false)?;
+    ///     Ok(())
+    /// }
+    /// ```
+    fn set_raw_mode(&self, raw: bool) -> io::Result<()> {
+        crate::sys::io::set_raw_mode(self.as_fd(), raw)
+    }
+
+    /// Returns whether the terminal is currently in raw mode, see [`set_raw_mode`].
+    ///
+    /// [`set_raw_mode`]: TerminalExt::set_raw_mode
+    fn is_raw_mode(&self) -> io::Result<bool> {
+        crate::sys::io::is_raw_mode(self.as_fd())
+    }
+
+    /// Returns the size of the terminal.
+    ///
+    /// This currently always fails with [`io::ErrorKind::Unsupported`], since the
+    /// kernel doesn't expose the console size yet.
+    fn window_size(&self) -> io::Result<WindowSize> {
+        let (rows, columns) = crate::sys::io::window_size(self.as_fd())?;
+        Ok(WindowSize { rows, columns })
+    }
+}
+
+#[unstable(feature = "emerald_terminal", issue = "none")]
+impl<T: AsFd> TerminalExt for T {}
@@ -1,8 +1,10 @@
 #![stable(feature = "rust1", since = "1.0.0")]
 
 pub mod ffi;
+pub mod fs;
 pub mod io;
 pub mod process;
+pub mod rand;
 
 /// A prelude for conveniently writing platform-specific code.
 ///
@@ -12,4 +14,7 @@ pub mod prelude {
     #[doc(no_inline)]
     #[stable(feature = "rust1", since = "1.0.0")]
     pub use super::ffi::{OsStrExt, OsStringExt};
+    #[doc(no_inline)]
+    #[stable(feature = "rust1", since = "1.0.0")]
+    pub use super::fs::{FileExt, PermissionsExt};
 }
@@ -0,0 +1,31 @@
+//! Emerald-specific random number generation.
+
+#![unstable(feature = "emerald_rand", issue = "none")]
+
+use crate::io;
+
+/// Fills `buf` with random bytes from the CPU's hardware random number generator.
+///
+/// Emerald doesn't have a kernel entropy source yet, so this uses `rdrand`. It fails with
+/// [`io::ErrorKind::Unsupported`] when the CPU doesn't have it, instead of falling back to a
+/// predictable generator like the keys of [`HashMap`] do.
+///
+/// [`HashMap`]: crate::collections::HashMap
+///
+/// # Examples
+///
+/// ```no_run
+/// #![feature(emerald_rand)]
+/// use std::os::emerald::rand::fill_bytes;
+///
+/// fn main() -> std::io::Result<()> {
+///     let mut key = [0u8; 32];
+///     fill_bytes(&mut key)?;
+///     println!("key: {key:?}");
+///     Ok(())
+/// }
+/// ```
+#[unstable(feature = "emerald_rand", issue = "none")]
+pub fn fill_bytes(buf: &mut [u8]) -> io::Result<()> {
+    crate::sys::rand::fill_bytes(buf)
+}
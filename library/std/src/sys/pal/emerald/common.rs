@@ -1,21 +1,30 @@
-use super::args;
+use super::{args, os, rand};
 
 // SAFETY: must be called only once during runtime initialization.
 // NOTE: this is not guaranteed to run, for example when Rust code is called externally.
 pub unsafe fn init(argc: isize, argv: *const *const u8, _sigpipe: u8) {
     unsafe { args::init(argc, argv) }
+    os::init_environment();
 }
 
 // SAFETY: must be called only once during runtime cleanup.
 // NOTE: this is not guaranteed to run, for example when the program aborts.
 pub unsafe fn cleanup() {}
 
+pub fn unsupported<T>() -> crate::io::Result<T> {
+    Err(unsupported_err())
+}
+
+pub fn unsupported_err() -> crate::io::Error {
+    crate::io::Error::UNSUPPORTED_PLATFORM
+}
+
 pub fn is_interrupted(_code: i32) -> bool {
     false
 }
 
-pub fn decode_error_kind(_code: i32) -> crate::io::ErrorKind {
-    crate::io::ErrorKind::Uncategorized
+pub fn decode_error_kind(code: i32) -> crate::io::ErrorKind {
+    super::error_kind(code)
 }
 
 pub fn abort_internal() -> ! {
@@ -23,5 +32,7 @@ pub fn abort_internal() -> ! {
 }
 
 pub fn hashmap_random_keys() -> (u64, u64) {
-    (1, 2)
+    // NOTE: on CPUs without `rdrand` these come from `rand::fallback_u64`, which is
+    //       predictable, so `HashMap` is not resistant to HashDoS attacks there
+    (rand::random_u64(), rand::random_u64())
 }
@@ -8,7 +8,7 @@
     sys_common::{AsInner, FromInner, IntoInner},
 };
 
-use emerald_std::io::FileMeta;
+use emerald_std::io::{BlockingMode, FileMeta, SeekWhence};
 
 use super::syscall_to_io_error;
 
@@ -62,17 +62,10 @@ pub fn read_to_end(&self, buf: &mut Vec<u8>) -> io::Result<usize> {
         (&mut me).read_to_end(buf)
     }
 
-    pub fn read_at(&self, _buf: &mut [u8], _offset: u64) -> io::Result<usize> {
-        // unsafe {
-        //     cvt(pread64(
-        //         self.as_raw_fd(),
-        //         buf.as_mut_ptr() as *mut libc::c_void,
-        //         cmp::min(buf.len(), READ_LIMIT),
-        //         offset as off64_t,
-        //     ))
-        //     .map(|n| n as usize)
-        // }
-        todo!()
+    pub fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        // TODO: there is no `pread` syscall yet, so emulate it by seeking and
+        //       restoring the cursor afterwards, this is fine since we only have one thread
+        self.with_offset(offset, || self.read(buf))
     }
 
     pub fn read_buf(&self, mut cursor: BorrowedCursor<'_>) -> io::Result<()> {
@@ -122,90 +115,52 @@ pub fn is_write_vectored(&self) -> bool {
         false
     }
 
-    pub fn write_at(&self, _buf: &[u8], _offset: u64) -> io::Result<usize> {
-        // #[cfg(not(any(
-        //     all(target_os = "linux", not(target_env = "musl")),
-        //     target_os = "android",
-        //     target_os = "hurd"
-        // )))]
-        // use libc::pwrite as pwrite64;
-        // #[cfg(any(
-        //     all(target_os = "linux", not(target_env = "musl")),
-        //     target_os = "android",
-        //     target_os = "hurd"
-        // ))]
-        // use libc::pwrite64;
-
-        // unsafe {
-        //     cvt(pwrite64(
-        //         self.as_raw_fd(),
-        //         buf.as_ptr() as *const libc::c_void,
-        //         cmp::min(buf.len(), READ_LIMIT),
-        //         offset as off64_t,
-        //     ))
-        //     .map(|n| n as usize)
-        // }
-        todo!()
+    pub fn write_at(&self, buf: &[u8], offset: u64) -> io::Result<usize> {
+        // TODO: there is no `pwrite` syscall yet, see `read_at`
+        self.with_offset(offset, || self.write(buf))
     }
 
     pub fn write_vectored_at(&self, bufs: &[IoSlice<'_>], offset: u64) -> io::Result<usize> {
         io::default_write_vectored(|b| self.write_at(b, offset), bufs)
     }
 
-    #[cfg(not(any(
-        target_env = "newlib",
-        target_os = "solaris",
-        target_os = "illumos",
-        target_os = "emscripten",
-        target_os = "fuchsia",
-        target_os = "l4re",
-        target_os = "linux",
-        target_os = "haiku",
-        target_os = "redox",
-        target_os = "vxworks",
-        target_os = "nto",
-    )))]
-    pub fn set_cloexec(&self) -> io::Result<()> {
-        // unsafe {
-        //     cvt(libc::ioctl(self.as_raw_fd(), libc::FIOCLEX))?;
-        //     Ok(())
-        // }
-        todo!()
-    }
-    #[cfg(any(
-        all(
-            target_env = "newlib",
-            not(any(target_os = "espidf", target_os = "horizon", target_os = "vita"))
-        ),
-        target_os = "solaris",
-        target_os = "illumos",
-        target_os = "emscripten",
-        target_os = "fuchsia",
-        target_os = "l4re",
-        target_os = "linux",
-        target_os = "haiku",
-        target_os = "redox",
-        target_os = "vxworks",
-        target_os = "nto",
-    ))]
-    pub fn set_cloexec(&self) -> io::Result<()> {
+    fn seek(&self, whence: SeekWhence, offset: i64) -> io::Result<u64> {
+        let seek = emerald_std::io::SeekFrom { whence, offset };
         unsafe {
-            let previous = cvt(libc::fcntl(self.as_raw_fd(), libc::F_GETFD))?;
-            let new = previous | libc::FD_CLOEXEC;
-            if new != previous {
-                cvt(libc::fcntl(self.as_raw_fd(), libc::F_SETFD, new))?;
-            }
-            Ok(())
+            emerald_std::io::syscall_seek(self.as_raw_fd(), seek).map_err(syscall_to_io_error)
         }
     }
 
+    /// Runs `f` with the cursor at `offset`, and tries to restore the previous cursor
+    /// position afterwards, even if `f` fails.
+    ///
+    /// The result of `f` is always returned, a read or write that already happened
+    /// shouldn't be reported as failed because the cursor couldn't be moved back.
+    fn with_offset<T>(&self, offset: u64, f: impl FnOnce() -> io::Result<T>) -> io::Result<T> {
+        let offset = i64::try_from(offset)
+            .map_err(|_| io::const_io_error!(io::ErrorKind::InvalidInput, "offset is too large"))?;
+        let position = self.seek(SeekWhence::Current, 0)?;
+        self.seek(SeekWhence::Start, offset)?;
+        let result = f();
+        let _ = self.seek(SeekWhence::Start, position as i64);
+        result
+    }
+
+    pub fn set_cloexec(&self) -> io::Result<()> {
+        // Nothing to do, a spawned process doesn't inherit any fds, only the ones
+        // passed explicitly with `SpawnFileMapping` are given to it.
+        Ok(())
+    }
+
     pub fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
         let blocking_mode = if nonblocking {
-            emerald_std::io::BlockingMode::None
+            BlockingMode::None
+        } else if super::io::try_is_terminal(self.as_fd())? {
+            // go back to line buffering, or to raw mode if it was set before
+            super::io::terminal_blocking_mode(self.as_fd())
         } else {
-            todo!("Not sure which mode to put here")
-            // emerald_std::io::BlockingMode::Line
-            // emerald_std::io::BlockingMode::Block(1)
+            // block until at least one byte is available, like a normal blocking `read`
+            BlockingMode::Block(1)
         };
 
         unsafe {
@@ -221,8 +176,8 @@ pub fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
 
     #[inline]
     pub fn duplicate(&self) -> io::Result<FileDesc> {
-        // Ok(Self(self.0.try_clone()?))
-        todo!()
+        // TODO: there is no syscall to duplicate fds yet, see `BorrowedFd::try_clone_to_owned`
+        Ok(Self(self.0.try_clone()?))
     }
 }
 
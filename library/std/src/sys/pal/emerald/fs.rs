@@ -1,3 +1,4 @@
+use core::cmp;
 use core::ffi::CStr;
 
 use emerald_std::io::{FileStat, SeekWhence};
@@ -8,17 +9,20 @@
 use crate::io::{self, BorrowedCursor, IoSlice, IoSliceMut, SeekFrom};
 use crate::os::emerald::io::{AsFd, AsRawFd, BorrowedFd, FromRawFd, IntoRawFd, OwnedFd, RawFd};
 use crate::os::emerald::prelude::OsStringExt;
-use crate::path::{Path, PathBuf};
+use crate::path::{Component, Path, PathBuf};
 use crate::sys::common::small_c_string::run_path_with_cstr;
 use crate::sys::time::SystemTime;
 use crate::sys_common::{AsInner, AsInnerMut, FromInner, IntoInner};
 
 use super::fd::FileDesc;
-use super::syscall_to_io_error;
+use super::{syscall_to_io_error, unsupported};
 
 pub struct File {
+    // absolute, so it still refers to the same file after `chdir`, empty when
+    // the file was created from a raw fd
     path: PathBuf,
     fd: FileDesc,
+    writable: bool,
 }
 
 #[derive(Clone, PartialEq, Eq, Hash, Debug)]
@@ -40,9 +44,15 @@ pub struct DirEntry {
 pub struct OpenOptions(emerald_std::io::OpenOptions);
 
 #[derive(Copy, Clone, Debug, Default)]
-pub struct FileTimes {}
+pub struct FileTimes {
+    accessed: Option<SystemTime>,
+    modified: Option<SystemTime>,
+}
 
-pub struct FilePermissions(!);
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct FilePermissions {
+    mode: u32,
+}
 
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
 pub struct FileType(emerald_std::io::FileType);
@@ -56,59 +66,76 @@ pub fn size(&self) -> u64 {
     }
 
     pub fn perm(&self) -> FilePermissions {
-        todo!()
+        // TODO: the kernel doesn't have any permissions attributes in `FileStat` yet,
+        //       so everything is readable and writable (and directories are searchable)
+        let mode =
+            if self.0.file_type == emerald_std::io::FileType::Directory { 0o777 } else { 0o666 };
+        FilePermissions { mode }
     }
 
     pub fn file_type(&self) -> FileType {
         FileType(self.0.file_type)
     }
 
+    // TODO: the kernel doesn't report any timestamps in `FileStat` yet
+
     pub fn modified(&self) -> io::Result<SystemTime> {
-        todo!()
+        Err(io::const_io_error!(
+            io::ErrorKind::Unsupported,
+            "modification time is not available on this platform currently",
+        ))
     }
 
     pub fn accessed(&self) -> io::Result<SystemTime> {
-        todo!()
+        Err(io::const_io_error!(
+            io::ErrorKind::Unsupported,
+            "access time is not available on this platform currently",
+        ))
     }
 
     pub fn created(&self) -> io::Result<SystemTime> {
-        todo!()
+        Err(io::const_io_error!(
+            io::ErrorKind::Unsupported,
+            "creation time is not available on this platform currently",
+        ))
     }
 }
 
 impl FilePermissions {
     pub fn readonly(&self) -> bool {
-        self.0
+        // check if any class (owner, group, others) has write permission
+        self.mode & 0o222 == 0
     }
 
-    pub fn set_readonly(&mut self, _readonly: bool) {
-        self.0
+    pub fn set_readonly(&mut self, readonly: bool) {
+        if readonly {
+            // remove write permission for all classes
+            self.mode &= !0o222;
+        } else {
+            // add write permission for all classes
+            self.mode |= 0o222;
+        }
     }
-}
 
-impl Clone for FilePermissions {
-    fn clone(&self) -> FilePermissions {
-        self.0
+    pub fn mode(&self) -> u32 {
+        self.mode
     }
 }
 
-impl PartialEq for FilePermissions {
-    fn eq(&self, _other: &FilePermissions) -> bool {
-        self.0
+impl FromInner<u32> for FilePermissions {
+    fn from_inner(mode: u32) -> FilePermissions {
+        FilePermissions { mode }
     }
 }
 
-impl Eq for FilePermissions {}
-
-impl fmt::Debug for FilePermissions {
-    fn fmt(&self, _f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        self.0
+impl FileTimes {
+    pub fn set_accessed(&mut self, t: SystemTime) {
+        self.accessed = Some(t);
     }
-}
 
-impl FileTimes {
-    pub fn set_accessed(&mut self, _t: SystemTime) {}
-    pub fn set_modified(&mut self, _t: SystemTime) {}
+    pub fn set_modified(&mut self, t: SystemTime) {
+        self.modified = Some(t);
+    }
 }
 
 impl FileType {
@@ -247,9 +274,11 @@ pub fn create_new(&mut self, create_new: bool) {
 
 impl File {
     pub fn open(path: &Path, opts: &OpenOptions) -> io::Result<File> {
-        let fd = run_path_with_cstr(path, &|path| Self::openc(path, opts))?;
+        // TODO: `is_absolute` is broken for us, check `os::current_exe`
+        let path = if !path.has_root() { super::os::getcwd()?.join(path) } else { path.to_owned() };
+        let fd = run_path_with_cstr(&path, &|path| Self::openc(path, opts))?;
 
-        Ok(File { path: path.to_owned(), fd })
+        Ok(File { path, fd, writable: opts.0.is_write() })
     }
 
     fn openc(path: &CStr, open_options: &OpenOptions) -> io::Result<FileDesc> {
@@ -272,15 +301,67 @@ pub fn file_attr(&self) -> io::Result<FileAttr> {
     }
 
     pub fn fsync(&self) -> io::Result<()> {
-        todo!()
+        // TODO: there is no syscall to sync the file to disk yet
+        unsupported()
     }
 
     pub fn datasync(&self) -> io::Result<()> {
-        todo!()
+        self.fsync()
+    }
+
+    pub fn truncate(&self, size: u64) -> io::Result<()> {
+        let position = self.seek(SeekFrom::Current(0))?;
+        let end = self.seek(SeekFrom::End(0))?;
+
+        // TODO: there is no syscall to shrink files yet, but files can be extended
+        //       by writing zeros at the end, which is what `ftruncate` does anyway.
+        //       Emptying the file is possible by opening it again with `truncate`
+        let result = if size == 0 && end != 0 {
+            self.truncate_to_zero()
+        } else if size < end {
+            unsupported()
+        } else {
+            self.write_zeros(size - end)
+        };
+
+        // restore the position even on failure, `set_len` must not move the cursor
+        self.seek(SeekFrom::Start(position))?;
+        result
     }
 
-    pub fn truncate(&self, _size: u64) -> io::Result<()> {
-        todo!()
+    fn truncate_to_zero(&self) -> io::Result<()> {
+        if !self.writable {
+            return Err(io::const_io_error!(
+                io::ErrorKind::InvalidInput,
+                "the file was not opened for writing",
+            ));
+        }
+        // we can't know which file a raw fd refers to
+        if self.path.as_os_str().is_empty() {
+            return unsupported();
+        }
+
+        let mut opts = OpenOptions::new();
+        opts.write(true);
+        opts.truncate(true);
+        // the new descriptor is only used to truncate the file, and is closed on drop
+        File::open(&self.path, &opts).map(drop)
+    }
+
+    fn write_zeros(&self, mut len: u64) -> io::Result<()> {
+        let zeros = [0u8; 512];
+        while len > 0 {
+            let chunk = cmp::min(len, zeros.len() as u64) as usize;
+            let written = self.write(&zeros[..chunk])?;
+            if written == 0 {
+                return Err(io::const_io_error!(
+                    io::ErrorKind::WriteZero,
+                    "failed to write whole buffer",
+                ));
+            }
+            len -= written as u64;
+        }
+        Ok(())
     }
 
     pub fn read(&self, buf: &mut [u8]) -> io::Result<usize> {
@@ -299,6 +380,14 @@ pub fn read_buf(&self, cursor: BorrowedCursor<'_>) -> io::Result<()> {
         self.fd.read_buf(cursor)
     }
 
+    pub fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        self.fd.read_at(buf, offset)
+    }
+
+    pub fn read_vectored_at(&self, bufs: &mut [IoSliceMut<'_>], offset: u64) -> io::Result<usize> {
+        self.fd.read_vectored_at(bufs, offset)
+    }
+
     pub fn write(&self, buf: &[u8]) -> io::Result<usize> {
         self.fd.write(buf)
     }
@@ -311,6 +400,14 @@ pub fn is_write_vectored(&self) -> bool {
         self.fd.is_write_vectored()
     }
 
+    pub fn write_at(&self, buf: &[u8], offset: u64) -> io::Result<usize> {
+        self.fd.write_at(buf, offset)
+    }
+
+    pub fn write_vectored_at(&self, bufs: &[IoSlice<'_>], offset: u64) -> io::Result<usize> {
+        self.fd.write_vectored_at(bufs, offset)
+    }
+
     #[inline]
     pub fn flush(&self) -> io::Result<()> {
         Ok(())
@@ -334,15 +431,19 @@ pub fn seek(&self, pos: SeekFrom) -> io::Result<u64> {
 
     pub fn duplicate(&self) -> io::Result<File> {
         let fd = self.fd.duplicate()?;
-        Ok(File { path: self.path.clone(), fd })
+        Ok(File { path: self.path.clone(), fd, writable: self.writable })
     }
 
-    pub fn set_permissions(&self, _perm: FilePermissions) -> io::Result<()> {
-        todo!()
+    pub fn set_permissions(&self, perm: FilePermissions) -> io::Result<()> {
+        set_file_perm(self.file_attr()?, perm)
     }
 
-    pub fn set_times(&self, _times: FileTimes) -> io::Result<()> {
-        todo!()
+    pub fn set_times(&self, times: FileTimes) -> io::Result<()> {
+        if times.accessed.is_none() && times.modified.is_none() {
+            return Ok(());
+        }
+        // TODO: timestamps are not stored by the kernel yet, see `FileAttr`
+        unsupported()
     }
 }
 
@@ -368,7 +469,7 @@ fn into_inner(self) -> FileDesc {
 
 impl FromInner<FileDesc> for File {
     fn from_inner(file_desc: FileDesc) -> Self {
-        Self { path: PathBuf::new(), fd: file_desc }
+        Self { path: PathBuf::new(), fd: file_desc, writable: false }
     }
 }
 
@@ -394,7 +495,11 @@ fn into_raw_fd(self) -> RawFd {
 
 impl FromRawFd for File {
     unsafe fn from_raw_fd(raw_fd: RawFd) -> Self {
-        File { path: PathBuf::new(), fd: unsafe { FromRawFd::from_raw_fd(raw_fd) } }
+        File {
+            path: PathBuf::new(),
+            fd: unsafe { FromRawFd::from_raw_fd(raw_fd) },
+            writable: false,
+        }
     }
 }
 
@@ -403,8 +508,14 @@ pub fn new() -> DirBuilder {
         DirBuilder {}
     }
 
-    pub fn mkdir(&self, _p: &Path) -> io::Result<()> {
-        todo!("mkdir")
+    pub fn mkdir(&self, p: &Path) -> io::Result<()> {
+        // TODO: there is no syscall to create directories yet, but at least report
+        //       existing paths correctly so that `create_dir_all` works for them
+        match stat(p) {
+            Ok(_) => Err(io::const_io_error!(io::ErrorKind::AlreadyExists, "Already exists")),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => unsupported(),
+            Err(e) => Err(e),
+        }
     }
 }
 
@@ -418,36 +529,57 @@ pub fn readdir(p: &Path) -> io::Result<ReadDir> {
     ReadDir::new(p)
 }
 
-pub fn unlink(_p: &Path) -> io::Result<()> {
-    todo!("unlink")
+pub fn unlink(p: &Path) -> io::Result<()> {
+    if stat(p)?.file_type().is_dir() {
+        return Err(io::const_io_error!(io::ErrorKind::IsADirectory, "Is a directory"));
+    }
+    // TODO: there is no syscall to remove files yet
+    unsupported()
+}
+
+pub fn rename(old: &Path, _new: &Path) -> io::Result<()> {
+    stat(old)?;
+    // TODO: there is no syscall to rename files yet
+    unsupported()
 }
 
-pub fn rename(_old: &Path, _new: &Path) -> io::Result<()> {
-    todo!("rename")
+pub fn set_perm(p: &Path, perm: FilePermissions) -> io::Result<()> {
+    set_file_perm(stat(p)?, perm)
 }
 
-pub fn set_perm(_p: &Path, perm: FilePermissions) -> io::Result<()> {
-    match perm.0 {}
+fn set_file_perm(attr: FileAttr, perm: FilePermissions) -> io::Result<()> {
+    // nothing to change, this is what `fs::copy` does for example
+    if attr.perm() == perm {
+        return Ok(());
+    }
+    // TODO: there is no syscall to change the permissions of a file yet
+    unsupported()
 }
 
-pub fn rmdir(_p: &Path) -> io::Result<()> {
-    todo!("rmdir")
+pub fn rmdir(p: &Path) -> io::Result<()> {
+    if !stat(p)?.file_type().is_dir() {
+        return Err(io::const_io_error!(io::ErrorKind::NotADirectory, "Is not a directory"));
+    }
+    // TODO: there is no syscall to remove directories yet
+    unsupported()
 }
 
 pub use crate::sys_common::fs::remove_dir_all;
 
 pub use crate::sys_common::fs::try_exists;
 
-pub fn readlink(_p: &Path) -> io::Result<PathBuf> {
-    todo!("readlink")
+pub fn readlink(p: &Path) -> io::Result<PathBuf> {
+    stat(p)?;
+    // There are no symlinks in Emerald, so every file is not a link
+    Err(io::const_io_error!(io::ErrorKind::InvalidInput, "Not a symbolic link"))
 }
 
 pub fn symlink(_original: &Path, _link: &Path) -> io::Result<()> {
-    todo!("symlink")
+    unsupported()
 }
 
 pub fn link(_src: &Path, _dst: &Path) -> io::Result<()> {
-    todo!("link")
+    unsupported()
 }
 
 pub fn stat(p: &Path) -> io::Result<FileAttr> {
@@ -468,10 +600,26 @@ pub fn lstat(p: &Path) -> io::Result<FileAttr> {
     stat(p)
 }
 
-pub fn canonicalize(_p: &Path) -> io::Result<PathBuf> {
-    todo!("canonicalize")
-}
+pub fn canonicalize(p: &Path) -> io::Result<PathBuf> {
+    // TODO: `is_absolute` is broken for us, check `os::current_exe`
+    let path = if !p.has_root() { super::os::getcwd()?.join(p) } else { p.to_owned() };
 
-pub fn copy(_from: &Path, _to: &Path) -> io::Result<u64> {
-    todo!("copy")
+    // There are no symlinks in Emerald, so resolving `.` and `..` is all we need to do
+    let mut canonical = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Prefix(_) | Component::RootDir => canonical.push(component),
+            Component::CurDir => {}
+            Component::ParentDir => {
+                canonical.pop();
+            }
+            Component::Normal(name) => canonical.push(name),
+        }
+    }
+
+    // the path must exist, same as `realpath`
+    stat(&canonical)?;
+    Ok(canonical)
 }
+
+pub use crate::sys_common::fs::copy;
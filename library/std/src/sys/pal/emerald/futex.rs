@@ -0,0 +1,37 @@
+use crate::sync::atomic::{AtomicU32, Ordering::Relaxed};
+use crate::time::Duration;
+
+use super::thread::Thread;
+
+/// Waits until the value of `futex` is no longer `expected`, or until the timeout expires.
+///
+/// Returns false on timeout, and true in all other cases (including spurious wakeups).
+pub fn futex_wait(futex: &AtomicU32, expected: u32, timeout: Option<Duration>) -> bool {
+    // TODO: there is no futex syscall yet. Processes only have one thread for now, so the
+    //       value can't change while we wait, and nothing could wake us up, sleep until the
+    //       timeout instead of checking the value again and again.
+    if futex.load(Relaxed) != expected {
+        return true;
+    }
+
+    match timeout {
+        Some(timeout) => {
+            Thread::sleep(timeout);
+            false
+        }
+        None => panic!("deadlock: waiting on a futex that no other thread can wake up"),
+    }
+}
+
+/// Wakes up one thread that's blocked on `futex_wait` on this futex.
+///
+/// There are no other threads that could be waiting (see `futex_wait`), so this
+/// never wakes up anything, and always returns false.
+#[inline]
+pub fn futex_wake(_futex: &AtomicU32) -> bool {
+    false
+}
+
+/// Wakes up all threads that are waiting on `futex_wait` on this futex.
+#[inline]
+pub fn futex_wake_all(_futex: &AtomicU32) {}
@@ -1,8 +1,11 @@
 use crate::mem;
 
-use crate::os::emerald::io::{AsFd, AsRawFd};
-use crate::sys::pal::emerald::syscall_to_io_error;
-use emerald_std::io::FileMeta;
+use crate::collections::BTreeSet;
+use crate::io;
+use crate::os::emerald::io::{AsFd, AsRawFd, BorrowedFd, RawFd};
+use crate::sync::Mutex;
+use crate::sys::pal::emerald::{syscall_to_io_error, unsupported};
+use emerald_std::io::{BlockingMode, FileMeta};
 
 #[derive(Copy, Clone)]
 pub struct IoSlice<'a>(&'a [u8]);
@@ -51,15 +54,105 @@ pub fn as_mut_slice(&mut self) -> &mut [u8] {
 }
 
 pub fn is_terminal(file: &impl AsFd) -> bool {
+    // an invalid or closed fd is not a terminal
+    try_is_terminal(file.as_fd()).unwrap_or(false)
+}
+
+pub fn try_is_terminal(fd: BorrowedFd<'_>) -> io::Result<bool> {
     let mut meta = FileMeta::IsTerminal(false);
     unsafe {
-        emerald_std::io::syscall_get_file_meta(file.as_fd().as_raw_fd(), &mut meta)
-            .map_err(syscall_to_io_error)
-            .expect("syscall_get_file_meta failed");
+        emerald_std::io::syscall_get_file_meta(fd.as_raw_fd(), &mut meta)
+            .map_err(syscall_to_io_error)?;
+    }
+
+    match meta {
+        FileMeta::IsTerminal(is_terminal) => Ok(is_terminal),
+        _ => unreachable!(),
+    }
+}
+
+fn ensure_terminal(fd: BorrowedFd<'_>) -> io::Result<()> {
+    if try_is_terminal(fd)? {
+        Ok(())
+    } else {
+        Err(io::const_io_error!(io::ErrorKind::InvalidInput, "not a terminal"))
+    }
+}
+
+// The kernel only keeps one `BlockingMode` per file, which is used both for raw mode and for
+// nonblocking reads, so remember which terminals are in raw mode, to know which mode to go
+// back to once they are blocking again.
+// Fds are removed from here when they are closed, see `forget_raw_mode`.
+static RAW_TERMINALS: Mutex<BTreeSet<RawFd>> = Mutex::new(BTreeSet::new());
+
+/// Called when `fd` is closed, so that a file opened later with the same fd number
+/// doesn't start in raw mode.
+pub fn forget_raw_mode(fd: RawFd) {
+    RAW_TERMINALS.lock().unwrap_or_else(|e| e.into_inner()).remove(&fd);
+}
+
+fn get_blocking_mode(fd: BorrowedFd<'_>) -> io::Result<BlockingMode> {
+    let mut meta = FileMeta::BlockingMode(BlockingMode::None);
+    unsafe {
+        emerald_std::io::syscall_get_file_meta(fd.as_raw_fd(), &mut meta)
+            .map_err(syscall_to_io_error)?;
     }
 
     match meta {
-        FileMeta::IsTerminal(is_terminal) => is_terminal,
+        FileMeta::BlockingMode(blocking_mode) => Ok(blocking_mode),
         _ => unreachable!(),
     }
 }
+
+// the blocking mode a terminal should use when it's not nonblocking
+pub fn terminal_blocking_mode(fd: BorrowedFd<'_>) -> BlockingMode {
+    // The console only has line buffering as a "mode", raw mode just returns the
+    // characters as soon as they are typed.
+    if RAW_TERMINALS.lock().unwrap().contains(&fd.as_raw_fd()) {
+        BlockingMode::Block(1)
+    } else {
+        BlockingMode::Line
+    }
+}
+
+pub fn set_raw_mode(fd: BorrowedFd<'_>, raw: bool) -> io::Result<()> {
+    ensure_terminal(fd)?;
+
+    // TODO: echo and special characters handling can't be controlled from userspace yet
+    let blocking_mode = if raw { BlockingMode::Block(1) } else { BlockingMode::Line };
+    // a nonblocking terminal stays nonblocking, the new mode is used once it blocks again
+    if get_blocking_mode(fd)? != BlockingMode::None {
+        unsafe {
+            emerald_std::io::syscall_set_file_meta(
+                fd.as_raw_fd(),
+                FileMeta::BlockingMode(blocking_mode),
+            )
+            .map_err(syscall_to_io_error)?;
+        }
+    }
+
+    let mut raw_terminals = RAW_TERMINALS.lock().unwrap();
+    if raw {
+        raw_terminals.insert(fd.as_raw_fd());
+    } else {
+        raw_terminals.remove(&fd.as_raw_fd());
+    }
+    Ok(())
+}
+
+pub fn is_raw_mode(fd: BorrowedFd<'_>) -> io::Result<bool> {
+    ensure_terminal(fd)?;
+
+    match get_blocking_mode(fd)? {
+        BlockingMode::Line => Ok(false),
+        BlockingMode::Block(_) => Ok(true),
+        // nonblocking, which says nothing about the mode it goes back to
+        BlockingMode::None => Ok(RAW_TERMINALS.lock().unwrap().contains(&fd.as_raw_fd())),
+    }
+}
+
+pub fn window_size(fd: BorrowedFd<'_>) -> io::Result<(u16, u16)> {
+    ensure_terminal(fd)?;
+    // TODO: there is no `FileMeta` to get the size of the console yet
+    unsupported()
+}
@@ -5,11 +5,13 @@
 pub mod env;
 pub mod fd;
 pub mod fs;
+pub mod futex;
 pub mod io;
 pub mod net;
 pub mod os;
 pub mod pipe;
 pub mod process;
+pub mod rand;
 pub mod stdio;
 pub mod thread;
 #[cfg(target_thread_local)]
@@ -20,69 +22,148 @@
 mod common;
 pub use common::*;
 
-use emerald_std::SyscallError;
+use crate::io::ErrorKind;
+use crate::sync::atomic::{AtomicI32, Ordering};
 
-fn syscall_to_io_error(e: SyscallError) -> crate::io::Error {
+use emerald_std::{SyscallArgError, SyscallError};
+
+/// The code used for `SyscallError::InvalidArgument`, the errors of each argument
+/// are stored in the upper bits, see `syscall_error_code`.
+const INVALID_ARGUMENT_CODE: i32 = 24;
+/// The code used for errors we don't know about.
+const INVALID_ERROR_CODE: i32 = 2;
+const ARG_ERROR_BITS: u32 = 3;
+
+/// The kind and message of every `SyscallError`, indexed by its error code minus one.
+///
+/// The codes are the discriminants of `SyscallError` plus one, so that `0` is left to mean
+/// no error like on other targets, and they are what is stored in `io::Error` as the raw os error.
+const ERRORS: [(ErrorKind, &str); 24] = [
+    (ErrorKind::Unsupported, "syscall not found"),
+    (ErrorKind::Uncategorized, "invalid error"),
+    (ErrorKind::NotFound, "could not open file"),
+    (ErrorKind::NotFound, "invalid file index"),
+    (ErrorKind::PermissionDenied, "could not write to file"),
+    (ErrorKind::PermissionDenied, "could not read from file"),
+    (ErrorKind::InvalidData, "could not load elf"),
+    (ErrorKind::OutOfMemory, "could not allocate process"),
+    (ErrorKind::OutOfMemory, "heap ranges exceeded"),
+    (ErrorKind::UnexpectedEof, "unexpected end of file"),
+    (ErrorKind::NotFound, "file not found"),
+    (ErrorKind::NotFound, "process with given pid not found"),
+    (ErrorKind::Uncategorized, "process with given pid is still running"),
+    (ErrorKind::NotADirectory, "is not a directory"),
+    (ErrorKind::IsADirectory, "is a directory"),
+    (ErrorKind::InvalidInput, "buffer too small"),
+    (ErrorKind::Unsupported, "graphics not available"),
+    (ErrorKind::ResourceBusy, "graphics already taken"),
+    (ErrorKind::PermissionDenied, "graphics not owned"),
+    (ErrorKind::InvalidInput, "invalid graphics buffer"),
+    (ErrorKind::InvalidInput, "invalid offset"),
+    (ErrorKind::AlreadyExists, "already exists"),
+    (ErrorKind::Unsupported, "operation not supported"),
+    (ErrorKind::InvalidInput, "invalid argument"),
+];
+
+/// The code of the last error returned from a syscall, used as `errno`.
+///
+/// This is process-wide and not per-thread, processes only have one thread for now.
+static LAST_ERROR: AtomicI32 = AtomicI32::new(0);
+
+fn syscall_error_code(e: SyscallError) -> i32 {
     match e {
-        SyscallError::PidNotFound => crate::io::Error::new(
-            crate::io::ErrorKind::NotFound,
-            "Process with given pid not found",
-        ),
-        SyscallError::ProcessStillRunning => crate::io::Error::new(
-            crate::io::ErrorKind::Other,
-            "Process with given pid is still running",
-        ),
-        SyscallError::EndOfFile => {
-            crate::io::Error::new(crate::io::ErrorKind::UnexpectedEof, "Unexpected end of file")
-        }
-        SyscallError::FileNotFound => {
-            crate::io::Error::new(crate::io::ErrorKind::NotFound, "File not found")
-        }
-        SyscallError::CouldNotOpenFile => {
-            crate::io::Error::new(crate::io::ErrorKind::NotFound, "Could not open file")
-        }
-        SyscallError::InvalidFileIndex => {
-            crate::io::Error::new(crate::io::ErrorKind::NotFound, "Invalid file index")
-        }
-        SyscallError::CouldNotWriteToFile => {
-            crate::io::Error::new(crate::io::ErrorKind::PermissionDenied, "Could not write to file")
-        }
-        SyscallError::CouldNotReadFromFile => crate::io::Error::new(
-            crate::io::ErrorKind::PermissionDenied,
-            "Could not read from file",
-        ),
-        SyscallError::IsNotDirectory => {
-            crate::io::Error::new(crate::io::ErrorKind::NotADirectory, "Is not a directory")
-        }
-        SyscallError::IsDirectory => {
-            crate::io::Error::new(crate::io::ErrorKind::IsADirectory, "Is a directory")
-        }
-        SyscallError::AlreadyExists => {
-            crate::io::Error::new(crate::io::ErrorKind::AlreadyExists, "Already exists")
-        }
-        SyscallError::OperationNotSupported => {
-            crate::io::Error::new(crate::io::ErrorKind::Unsupported, "Not supported")
-        }
+        SyscallError::SyscallNotFound => 1,
+        SyscallError::InvalidError => 2,
+        SyscallError::CouldNotOpenFile => 3,
+        SyscallError::InvalidFileIndex => 4,
+        SyscallError::CouldNotWriteToFile => 5,
+        SyscallError::CouldNotReadFromFile => 6,
+        SyscallError::CouldNotLoadElf => 7,
+        SyscallError::CouldNotAllocateProcess => 8,
+        SyscallError::HeapRangesExceeded => 9,
+        SyscallError::EndOfFile => 10,
+        SyscallError::FileNotFound => 11,
+        SyscallError::PidNotFound => 12,
+        SyscallError::ProcessStillRunning => 13,
+        SyscallError::IsNotDirectory => 14,
+        SyscallError::IsDirectory => 15,
+        SyscallError::BufferTooSmall => 16,
+        SyscallError::GraphicsNotAvailable => 17,
+        SyscallError::GraphicsAlreadyTaken => 18,
+        SyscallError::GraphicsNotOwned => 19,
+        SyscallError::InvalidGraphicsBuffer => 20,
+        SyscallError::InvalidOffset => 21,
+        SyscallError::AlreadyExists => 22,
+        SyscallError::OperationNotSupported => 23,
         SyscallError::InvalidArgument(arg1, arg2, arg3, arg4, arg5, arg6, arg7) => {
-            let errors = [arg1, arg2, arg3, arg4, arg5, arg6, arg7];
+            // pack the error of each argument in 3 bits after the code, `SyscallArgError`
+            // values are all below 8, and 0 is used for valid arguments
+            [arg1, arg2, arg3, arg4, arg5, arg6, arg7].iter().enumerate().fold(
+                INVALID_ARGUMENT_CODE,
+                |code, (i, e)| {
+                    let e = e.map_or(0, |e| e as i32 & ((1 << ARG_ERROR_BITS) - 1));
+                    code | e << (8 + i as u32 * ARG_ERROR_BITS)
+                },
+            )
+        }
+        // `SyscallError` is `non_exhaustive`, treat new errors as invalid until we handle them
+        _ => INVALID_ERROR_CODE,
+    }
+}
+
+fn syscall_to_io_error(e: SyscallError) -> crate::io::Error {
+    let code = syscall_error_code(e);
+    LAST_ERROR.store(code, Ordering::Relaxed);
+    crate::io::Error::from_raw_os_error(code)
+}
+
+fn last_error() -> i32 {
+    LAST_ERROR.load(Ordering::Relaxed)
+}
 
-            let mut error_str = String::new();
+fn error_entry(code: i32) -> Option<(ErrorKind, &'static str)> {
+    // only invalid argument errors have anything stored in the upper bits
+    let index = if code >= 0 && code & 0xFF == INVALID_ARGUMENT_CODE {
+        INVALID_ARGUMENT_CODE
+    } else {
+        code
+    };
+    ERRORS.get(usize::try_from(index).ok()?.checked_sub(1)?).copied()
+}
+
+fn error_kind(code: i32) -> ErrorKind {
+    error_entry(code).map_or(ErrorKind::Uncategorized, |(kind, _)| kind)
+}
 
-            errors.iter().take_while(|e| e.is_some()).enumerate().for_each(|(i, e)| {
-                if i != 0 {
-                    error_str.push_str(", ");
-                }
-                error_str.push_str(&format!("Arg{i}: {:?}", e.unwrap()));
-            });
+fn error_message(code: i32) -> String {
+    let Some((_, message)) = error_entry(code) else {
+        return format!("unknown error {code}");
+    };
 
-            crate::io::Error::new(crate::io::ErrorKind::InvalidInput, error_str)
+    if code & 0xFF != INVALID_ARGUMENT_CODE {
+        return message.to_string();
+    }
+
+    let mut args = String::new();
+    for i in 0..7 {
+        let arg = (code >> (8 + i * ARG_ERROR_BITS)) & ((1 << ARG_ERROR_BITS) - 1);
+        if arg == 0 {
+            continue;
+        }
+        if !args.is_empty() {
+            args.push_str(", ");
         }
-        // should never happen
-        SyscallError::SyscallNotFound | SyscallError::InvalidError => unreachable!(),
-        // not applicable
-        SyscallError::CouldNotLoadElf
-        | SyscallError::CouldNotAllocateProcess
-        | SyscallError::HeapRangesExceeded => unreachable!(),
-        _ => crate::io::Error::new(crate::io::ErrorKind::Other, "Unknown error"),
+        let arg_message = match arg {
+            a if a == SyscallArgError::GeneralInvalid as i32 => "invalid",
+            a if a == SyscallArgError::InvalidUserPointer as i32 => "invalid user pointer",
+            a if a == SyscallArgError::NotValidUtf8 as i32 => "not valid utf8",
+            a if a == SyscallArgError::InvalidHeapIncrement as i32 => "invalid heap increment",
+            a if a == SyscallArgError::DuplicateFileMappings as i32 => "duplicate file mappings",
+            a if a == SyscallArgError::InvalidNanoseconds as i32 => "invalid nanoseconds",
+            _ => "unknown",
+        };
+        args.push_str(&format!("arg{i}: {arg_message}"));
     }
+
+    if args.is_empty() { message.to_string() } else { format!("{message} ({args})") }
 }
@@ -1,334 +1,759 @@
+use crate::collections::{BTreeMap, VecDeque};
 use crate::fmt;
 use crate::io::{self, BorrowedCursor, IoSlice, IoSliceMut};
-use crate::net::{Ipv4Addr, Ipv6Addr, Shutdown, SocketAddr};
+use crate::net::{IpAddr, Ipv4Addr, Ipv6Addr, Shutdown, SocketAddr};
+use crate::sync::{Arc, Mutex, Weak};
 use crate::time::Duration;
+use crate::vec;
 
-pub struct TcpStream(!);
+use super::thread::Thread;
+use super::unsupported;
 
-impl TcpStream {
-    pub fn connect(_: io::Result<&SocketAddr>) -> io::Result<TcpStream> {
-        todo!("TcpStream::connect")
+// TODO: the kernel doesn't have a network stack or sockets syscalls yet, so this is a
+//       loopback only stack that lives inside the process. Sockets can only reach other
+//       sockets of the same process, through `127.0.0.1`, `::1` or the unspecified address.
+//
+// Processes only have one thread (see `Thread::new`), so nothing can arrive while a
+// blocking call waits. Instead of waiting forever, blocking calls fail with `Deadlock`
+// when there is nothing to return, or with `TimedOut` after sleeping for their timeout.
+
+const DEFAULT_TTL: u32 = 64;
+const EPHEMERAL_PORTS: crate::ops::RangeInclusive<u16> = 49152..=65535;
+
+// sockets are bound per IP version and port, since there is only the loopback interface
+type PortKey = (bool, u16);
+
+fn port_key(addr: &SocketAddr) -> PortKey {
+    (addr.is_ipv6(), addr.port())
+}
+
+struct Stack {
+    tcp_listeners: BTreeMap<PortKey, Weak<ListenerInner>>,
+    udp_sockets: BTreeMap<PortKey, Weak<UdpInner>>,
+    next_port: u16,
+}
+
+static STACK: Mutex<Stack> = Mutex::new(Stack {
+    tcp_listeners: BTreeMap::new(),
+    udp_sockets: BTreeMap::new(),
+    next_port: 0,
+});
+
+impl Stack {
+    fn is_bound<T>(sockets: &BTreeMap<PortKey, Weak<T>>, key: &PortKey) -> bool {
+        sockets.get(key).is_some_and(|socket| socket.strong_count() != 0)
+    }
+
+    fn tcp_port_free(&self, key: &PortKey) -> bool {
+        !Self::is_bound(&self.tcp_listeners, key)
+    }
+
+    fn udp_port_free(&self, key: &PortKey) -> bool {
+        !Self::is_bound(&self.udp_sockets, key)
+    }
+
+    /// Picks the port to bind `addr` to, replacing port `0` with a free ephemeral port.
+    fn bind_addr(
+        &mut self,
+        addr: &SocketAddr,
+        is_free: impl Fn(&Self, &PortKey) -> bool,
+    ) -> io::Result<SocketAddr> {
+        if !is_local(&addr.ip()) {
+            return Err(io::const_io_error!(
+                io::ErrorKind::AddrNotAvailable,
+                "only loopback addresses are available on this platform",
+            ));
+        }
+
+        let mut addr = *addr;
+        if addr.port() != 0 {
+            return if is_free(self, &port_key(&addr)) {
+                Ok(addr)
+            } else {
+                Err(io::const_io_error!(io::ErrorKind::AddrInUse, "address in use"))
+            };
+        }
+
+        for _ in EPHEMERAL_PORTS {
+            addr.set_port(self.ephemeral_port());
+            if is_free(self, &port_key(&addr)) {
+                return Ok(addr);
+            }
+        }
+        Err(io::const_io_error!(io::ErrorKind::AddrInUse, "no free ephemeral ports"))
+    }
+
+    fn ephemeral_port(&mut self) -> u16 {
+        let port = *EPHEMERAL_PORTS.start() + self.next_port;
+        self.next_port = (self.next_port + 1) % EPHEMERAL_PORTS.len() as u16;
+        port
     }
+}
+
+fn is_local(ip: &IpAddr) -> bool {
+    ip.is_loopback() || ip.is_unspecified()
+}
 
-    pub fn connect_timeout(_: &SocketAddr, _: Duration) -> io::Result<TcpStream> {
-        todo!("TcpStream::connect_timeout")
+fn loopback(addr: &SocketAddr) -> IpAddr {
+    match addr {
+        SocketAddr::V4(_) => Ipv4Addr::LOCALHOST.into(),
+        SocketAddr::V6(_) => Ipv6Addr::LOCALHOST.into(),
     }
+}
 
-    pub fn set_read_timeout(&self, _: Option<Duration>) -> io::Result<()> {
-        self.0
+fn check_timeout(timeout: Option<Duration>) -> io::Result<Option<Duration>> {
+    if timeout == Some(Duration::ZERO) {
+        return Err(io::const_io_error!(
+            io::ErrorKind::InvalidInput,
+            "cannot set a 0 duration timeout",
+        ));
+    }
+    Ok(timeout)
+}
+
+// the error for a call that has nothing to return yet, see the comment at the top
+fn would_block(nonblocking: bool, timeout: Option<Duration>) -> io::Error {
+    if nonblocking {
+        return io::const_io_error!(io::ErrorKind::WouldBlock, "operation would block");
+    }
+    match timeout {
+        Some(timeout) => {
+            Thread::sleep(timeout);
+            io::const_io_error!(io::ErrorKind::TimedOut, "operation timed out")
+        }
+        None => io::const_io_error!(
+            io::ErrorKind::Deadlock,
+            "operation would block forever, there are no other threads to wake it up",
+        ),
     }
+}
 
-    pub fn set_write_timeout(&self, _: Option<Duration>) -> io::Result<()> {
-        self.0
+////////////////////////////////////////////////////////////////////////////////
+// TCP streams
+////////////////////////////////////////////////////////////////////////////////
+
+// one direction of a connection
+#[derive(Default)]
+struct Channel {
+    data: VecDeque<u8>,
+    // the writer shut down or closed its side
+    write_closed: bool,
+    // the reader shut down or closed its side
+    read_closed: bool,
+}
+
+struct StreamOptions {
+    nonblocking: bool,
+    read_timeout: Option<Duration>,
+    write_timeout: Option<Duration>,
+    linger: Option<Duration>,
+    nodelay: bool,
+    ttl: u32,
+}
+
+struct StreamInner {
+    local: SocketAddr,
+    peer: SocketAddr,
+    rx: Arc<Mutex<Channel>>,
+    tx: Arc<Mutex<Channel>>,
+    options: Mutex<StreamOptions>,
+}
+
+impl Drop for StreamInner {
+    fn drop(&mut self) {
+        self.rx.lock().unwrap().read_closed = true;
+        self.tx.lock().unwrap().write_closed = true;
+    }
+}
+
+pub struct TcpStream(Arc<StreamInner>);
+
+impl TcpStream {
+    fn new(
+        local: SocketAddr,
+        peer: SocketAddr,
+        rx: Arc<Mutex<Channel>>,
+        tx: Arc<Mutex<Channel>>,
+    ) -> Self {
+        TcpStream(Arc::new(StreamInner {
+            local,
+            peer,
+            rx,
+            tx,
+            options: Mutex::new(StreamOptions {
+                nonblocking: false,
+                read_timeout: None,
+                write_timeout: None,
+                linger: None,
+                nodelay: false,
+                ttl: DEFAULT_TTL,
+            }),
+        }))
+    }
+
+    pub fn connect(addr: io::Result<&SocketAddr>) -> io::Result<TcpStream> {
+        let addr = addr?;
+        if !is_local(&addr.ip()) {
+            return Err(io::const_io_error!(
+                io::ErrorKind::NetworkUnreachable,
+                "only loopback addresses are reachable on this platform",
+            ));
+        }
+
+        let mut stack = STACK.lock().unwrap();
+        let listener =
+            stack.tcp_listeners.get(&port_key(addr)).and_then(Weak::upgrade).ok_or(
+                io::const_io_error!(io::ErrorKind::ConnectionRefused, "connection refused"),
+            )?;
+
+        // connecting to the unspecified address connects to the loopback address
+        let server = SocketAddr::new(loopback(addr), addr.port());
+        let client = SocketAddr::new(loopback(addr), stack.ephemeral_port());
+        drop(stack);
+
+        let to_server = Arc::new(Mutex::new(Channel::default()));
+        let to_client = Arc::new(Mutex::new(Channel::default()));
+        let accepted = TcpStream::new(server, client, to_server.clone(), to_client.clone());
+        listener.backlog.lock().unwrap().push_back(accepted);
+
+        Ok(TcpStream::new(client, server, to_client, to_server))
+    }
+
+    pub fn connect_timeout(addr: &SocketAddr, timeout: Duration) -> io::Result<TcpStream> {
+        check_timeout(Some(timeout))?;
+        // connecting never waits
+        TcpStream::connect(Ok(addr))
+    }
+
+    pub fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.0.options.lock().unwrap().read_timeout = check_timeout(timeout)?;
+        Ok(())
+    }
+
+    pub fn set_write_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.0.options.lock().unwrap().write_timeout = check_timeout(timeout)?;
+        Ok(())
     }
 
     pub fn read_timeout(&self) -> io::Result<Option<Duration>> {
-        self.0
+        Ok(self.0.options.lock().unwrap().read_timeout)
     }
 
     pub fn write_timeout(&self) -> io::Result<Option<Duration>> {
-        self.0
+        Ok(self.0.options.lock().unwrap().write_timeout)
     }
 
-    pub fn peek(&self, _: &mut [u8]) -> io::Result<usize> {
-        self.0
+    fn recv(&self, buf: &mut [u8], consume: bool) -> io::Result<usize> {
+        let mut rx = self.0.rx.lock().unwrap();
+        if buf.is_empty() || rx.read_closed {
+            return Ok(0);
+        }
+        if rx.data.is_empty() {
+            if rx.write_closed {
+                return Ok(0);
+            }
+            drop(rx);
+            let options = self.0.options.lock().unwrap();
+            return Err(would_block(options.nonblocking, options.read_timeout));
+        }
+
+        let n = crate::cmp::min(buf.len(), rx.data.len());
+        for (dst, src) in buf.iter_mut().zip(rx.data.iter()) {
+            *dst = *src;
+        }
+        if consume {
+            rx.data.drain(..n);
+        }
+        Ok(n)
     }
 
-    pub fn read(&self, _: &mut [u8]) -> io::Result<usize> {
-        self.0
+    pub fn peek(&self, buf: &mut [u8]) -> io::Result<usize> {
+        self.recv(buf, false)
     }
 
-    pub fn read_buf(&self, _buf: BorrowedCursor<'_>) -> io::Result<()> {
-        self.0
+    pub fn read(&self, buf: &mut [u8]) -> io::Result<usize> {
+        self.recv(buf, true)
     }
 
-    pub fn read_vectored(&self, _: &mut [IoSliceMut<'_>]) -> io::Result<usize> {
-        self.0
+    pub fn read_buf(&self, cursor: BorrowedCursor<'_>) -> io::Result<()> {
+        io::default_read_buf(|buf| self.read(buf), cursor)
+    }
+
+    pub fn read_vectored(&self, bufs: &mut [IoSliceMut<'_>]) -> io::Result<usize> {
+        io::default_read_vectored(|buf| self.read(buf), bufs)
     }
 
     pub fn is_read_vectored(&self) -> bool {
-        self.0
+        false
     }
 
-    pub fn write(&self, _: &[u8]) -> io::Result<usize> {
-        self.0
+    pub fn write(&self, buf: &[u8]) -> io::Result<usize> {
+        let mut tx = self.0.tx.lock().unwrap();
+        if tx.write_closed || tx.read_closed {
+            return Err(io::const_io_error!(io::ErrorKind::BrokenPipe, "broken pipe"));
+        }
+        // the buffer is unbounded, so writing never blocks
+        tx.data.extend(buf);
+        Ok(buf.len())
     }
 
-    pub fn write_vectored(&self, _: &[IoSlice<'_>]) -> io::Result<usize> {
-        self.0
+    pub fn write_vectored(&self, bufs: &[IoSlice<'_>]) -> io::Result<usize> {
+        io::default_write_vectored(|buf| self.write(buf), bufs)
     }
 
     pub fn is_write_vectored(&self) -> bool {
-        self.0
+        false
     }
 
     pub fn peer_addr(&self) -> io::Result<SocketAddr> {
-        self.0
+        Ok(self.0.peer)
     }
 
     pub fn socket_addr(&self) -> io::Result<SocketAddr> {
-        self.0
+        Ok(self.0.local)
     }
 
-    pub fn shutdown(&self, _: Shutdown) -> io::Result<()> {
-        self.0
+    pub fn shutdown(&self, how: Shutdown) -> io::Result<()> {
+        if matches!(how, Shutdown::Read | Shutdown::Both) {
+            self.0.rx.lock().unwrap().read_closed = true;
+        }
+        if matches!(how, Shutdown::Write | Shutdown::Both) {
+            self.0.tx.lock().unwrap().write_closed = true;
+        }
+        Ok(())
     }
 
     pub fn duplicate(&self) -> io::Result<TcpStream> {
-        self.0
+        Ok(TcpStream(self.0.clone()))
     }
 
-    pub fn set_linger(&self, _: Option<Duration>) -> io::Result<()> {
-        self.0
+    pub fn set_linger(&self, linger: Option<Duration>) -> io::Result<()> {
+        self.0.options.lock().unwrap().linger = linger;
+        Ok(())
     }
 
     pub fn linger(&self) -> io::Result<Option<Duration>> {
-        self.0
+        Ok(self.0.options.lock().unwrap().linger)
     }
 
-    pub fn set_nodelay(&self, _: bool) -> io::Result<()> {
-        self.0
+    pub fn set_nodelay(&self, nodelay: bool) -> io::Result<()> {
+        self.0.options.lock().unwrap().nodelay = nodelay;
+        Ok(())
     }
 
     pub fn nodelay(&self) -> io::Result<bool> {
-        self.0
+        Ok(self.0.options.lock().unwrap().nodelay)
     }
 
-    pub fn set_ttl(&self, _: u32) -> io::Result<()> {
-        self.0
+    pub fn set_ttl(&self, ttl: u32) -> io::Result<()> {
+        self.0.options.lock().unwrap().ttl = ttl;
+        Ok(())
     }
 
     pub fn ttl(&self) -> io::Result<u32> {
-        self.0
+        Ok(self.0.options.lock().unwrap().ttl)
     }
 
     pub fn take_error(&self) -> io::Result<Option<io::Error>> {
-        self.0
+        Ok(None)
     }
 
-    pub fn set_nonblocking(&self, _: bool) -> io::Result<()> {
-        self.0
+    pub fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        self.0.options.lock().unwrap().nonblocking = nonblocking;
+        Ok(())
     }
 }
 
 impl fmt::Debug for TcpStream {
-    fn fmt(&self, _f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        self.0
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TcpStream")
+            .field("addr", &self.0.local)
+            .field("peer", &self.0.peer)
+            .finish()
     }
 }
 
-pub struct TcpListener(!);
+////////////////////////////////////////////////////////////////////////////////
+// TCP listeners
+////////////////////////////////////////////////////////////////////////////////
+
+struct ListenerOptions {
+    nonblocking: bool,
+    only_v6: bool,
+    ttl: u32,
+}
+
+struct ListenerInner {
+    local: SocketAddr,
+    // connections that were not accepted yet
+    backlog: Mutex<VecDeque<TcpStream>>,
+    options: Mutex<ListenerOptions>,
+}
+
+pub struct TcpListener(Arc<ListenerInner>);
 
 impl TcpListener {
-    pub fn bind(_: io::Result<&SocketAddr>) -> io::Result<TcpListener> {
-        todo!("TcpListener::bind")
+    pub fn bind(addr: io::Result<&SocketAddr>) -> io::Result<TcpListener> {
+        let addr = addr?;
+        let mut stack = STACK.lock().unwrap();
+        let local = stack.bind_addr(addr, Stack::tcp_port_free)?;
+
+        let inner = Arc::new(ListenerInner {
+            local,
+            backlog: Mutex::new(VecDeque::new()),
+            options: Mutex::new(ListenerOptions {
+                nonblocking: false,
+                only_v6: false,
+                ttl: DEFAULT_TTL,
+            }),
+        });
+        stack.tcp_listeners.insert(port_key(&local), Arc::downgrade(&inner));
+        Ok(TcpListener(inner))
     }
 
     pub fn socket_addr(&self) -> io::Result<SocketAddr> {
-        self.0
+        Ok(self.0.local)
     }
 
     pub fn accept(&self) -> io::Result<(TcpStream, SocketAddr)> {
-        self.0
+        match self.0.backlog.lock().unwrap().pop_front() {
+            Some(stream) => {
+                let peer = stream.0.peer;
+                Ok((stream, peer))
+            }
+            None => Err(would_block(self.0.options.lock().unwrap().nonblocking, None)),
+        }
     }
 
     pub fn duplicate(&self) -> io::Result<TcpListener> {
-        self.0
+        Ok(TcpListener(self.0.clone()))
     }
 
-    pub fn set_ttl(&self, _: u32) -> io::Result<()> {
-        self.0
+    pub fn set_ttl(&self, ttl: u32) -> io::Result<()> {
+        self.0.options.lock().unwrap().ttl = ttl;
+        Ok(())
     }
 
     pub fn ttl(&self) -> io::Result<u32> {
-        self.0
+        Ok(self.0.options.lock().unwrap().ttl)
     }
 
-    pub fn set_only_v6(&self, _: bool) -> io::Result<()> {
-        self.0
+    pub fn set_only_v6(&self, only_v6: bool) -> io::Result<()> {
+        self.0.options.lock().unwrap().only_v6 = only_v6;
+        Ok(())
     }
 
     pub fn only_v6(&self) -> io::Result<bool> {
-        self.0
+        Ok(self.0.options.lock().unwrap().only_v6)
     }
 
     pub fn take_error(&self) -> io::Result<Option<io::Error>> {
-        self.0
+        Ok(None)
     }
 
-    pub fn set_nonblocking(&self, _: bool) -> io::Result<()> {
-        self.0
+    pub fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        self.0.options.lock().unwrap().nonblocking = nonblocking;
+        Ok(())
     }
 }
 
 impl fmt::Debug for TcpListener {
-    fn fmt(&self, _f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        self.0
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TcpListener").field("addr", &self.0.local).finish()
     }
 }
 
-pub struct UdpSocket(!);
+////////////////////////////////////////////////////////////////////////////////
+// UDP sockets
+////////////////////////////////////////////////////////////////////////////////
+
+struct UdpState {
+    datagrams: VecDeque<(Vec<u8>, SocketAddr)>,
+    peer: Option<SocketAddr>,
+    nonblocking: bool,
+    read_timeout: Option<Duration>,
+    write_timeout: Option<Duration>,
+    broadcast: bool,
+    multicast_loop_v4: bool,
+    multicast_ttl_v4: u32,
+    multicast_loop_v6: bool,
+    ttl: u32,
+}
+
+struct UdpInner {
+    local: SocketAddr,
+    state: Mutex<UdpState>,
+}
+
+pub struct UdpSocket(Arc<UdpInner>);
 
 impl UdpSocket {
-    pub fn bind(_: io::Result<&SocketAddr>) -> io::Result<UdpSocket> {
-        todo!("UdpSocket::bind")
+    pub fn bind(addr: io::Result<&SocketAddr>) -> io::Result<UdpSocket> {
+        let addr = addr?;
+        let mut stack = STACK.lock().unwrap();
+        let local = stack.bind_addr(addr, Stack::udp_port_free)?;
+
+        let inner = Arc::new(UdpInner {
+            local,
+            state: Mutex::new(UdpState {
+                datagrams: VecDeque::new(),
+                peer: None,
+                nonblocking: false,
+                read_timeout: None,
+                write_timeout: None,
+                broadcast: false,
+                multicast_loop_v4: true,
+                multicast_ttl_v4: 1,
+                multicast_loop_v6: true,
+                ttl: DEFAULT_TTL,
+            }),
+        });
+        stack.udp_sockets.insert(port_key(&local), Arc::downgrade(&inner));
+        Ok(UdpSocket(inner))
     }
 
     pub fn peer_addr(&self) -> io::Result<SocketAddr> {
         self.0
+            .state
+            .lock()
+            .unwrap()
+            .peer
+            .ok_or(io::const_io_error!(io::ErrorKind::NotConnected, "socket is not connected"))
     }
 
     pub fn socket_addr(&self) -> io::Result<SocketAddr> {
-        self.0
+        Ok(self.0.local)
     }
 
-    pub fn recv_from(&self, _: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
-        self.0
+    fn recv_datagram(&self, buf: &mut [u8], consume: bool) -> io::Result<(usize, SocketAddr)> {
+        let mut state = self.0.state.lock().unwrap();
+        let datagram =
+            if consume { state.datagrams.pop_front() } else { state.datagrams.front().cloned() };
+        let Some((datagram, from)) = datagram else {
+            let (nonblocking, timeout) = (state.nonblocking, state.read_timeout);
+            drop(state);
+            return Err(would_block(nonblocking, timeout));
+        };
+
+        // like on other platforms, the rest of a datagram that doesn't fit is discarded
+        let n = crate::cmp::min(buf.len(), datagram.len());
+        buf[..n].copy_from_slice(&datagram[..n]);
+        Ok((n, from))
     }
 
-    pub fn peek_from(&self, _: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
-        self.0
+    pub fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        self.recv_datagram(buf, true)
     }
 
-    pub fn send_to(&self, _: &[u8], _: &SocketAddr) -> io::Result<usize> {
-        self.0
+    pub fn peek_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        self.recv_datagram(buf, false)
+    }
+
+    pub fn send_to(&self, buf: &[u8], addr: &SocketAddr) -> io::Result<usize> {
+        if !is_local(&addr.ip()) {
+            return Err(io::const_io_error!(
+                io::ErrorKind::NetworkUnreachable,
+                "only loopback addresses are reachable on this platform",
+            ));
+        }
+
+        let target = STACK.lock().unwrap().udp_sockets.get(&port_key(addr)).and_then(Weak::upgrade);
+        // datagrams sent to a port nobody is bound to are lost, like on a real network
+        if let Some(target) = target {
+            let from = SocketAddr::new(loopback(&self.0.local), self.0.local.port());
+            let mut state = target.state.lock().unwrap();
+            // connected sockets only receive from their peer
+            if state.peer.map_or(true, |peer| peer == from) {
+                state.datagrams.push_back((buf.to_vec(), from));
+            }
+        }
+        Ok(buf.len())
     }
 
     pub fn duplicate(&self) -> io::Result<UdpSocket> {
-        self.0
+        Ok(UdpSocket(self.0.clone()))
     }
 
-    pub fn set_read_timeout(&self, _: Option<Duration>) -> io::Result<()> {
-        self.0
+    pub fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.0.state.lock().unwrap().read_timeout = check_timeout(timeout)?;
+        Ok(())
     }
 
-    pub fn set_write_timeout(&self, _: Option<Duration>) -> io::Result<()> {
-        self.0
+    pub fn set_write_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.0.state.lock().unwrap().write_timeout = check_timeout(timeout)?;
+        Ok(())
     }
 
     pub fn read_timeout(&self) -> io::Result<Option<Duration>> {
-        self.0
+        Ok(self.0.state.lock().unwrap().read_timeout)
     }
 
     pub fn write_timeout(&self) -> io::Result<Option<Duration>> {
-        self.0
+        Ok(self.0.state.lock().unwrap().write_timeout)
     }
 
-    pub fn set_broadcast(&self, _: bool) -> io::Result<()> {
-        self.0
+    pub fn set_broadcast(&self, broadcast: bool) -> io::Result<()> {
+        self.0.state.lock().unwrap().broadcast = broadcast;
+        Ok(())
     }
 
     pub fn broadcast(&self) -> io::Result<bool> {
-        self.0
+        Ok(self.0.state.lock().unwrap().broadcast)
     }
 
-    pub fn set_multicast_loop_v4(&self, _: bool) -> io::Result<()> {
-        self.0
+    pub fn set_multicast_loop_v4(&self, multicast_loop_v4: bool) -> io::Result<()> {
+        self.0.state.lock().unwrap().multicast_loop_v4 = multicast_loop_v4;
+        Ok(())
     }
 
     pub fn multicast_loop_v4(&self) -> io::Result<bool> {
-        self.0
+        Ok(self.0.state.lock().unwrap().multicast_loop_v4)
     }
 
-    pub fn set_multicast_ttl_v4(&self, _: u32) -> io::Result<()> {
-        self.0
+    pub fn set_multicast_ttl_v4(&self, multicast_ttl_v4: u32) -> io::Result<()> {
+        self.0.state.lock().unwrap().multicast_ttl_v4 = multicast_ttl_v4;
+        Ok(())
     }
 
     pub fn multicast_ttl_v4(&self) -> io::Result<u32> {
-        self.0
+        Ok(self.0.state.lock().unwrap().multicast_ttl_v4)
     }
 
-    pub fn set_multicast_loop_v6(&self, _: bool) -> io::Result<()> {
-        self.0
+    pub fn set_multicast_loop_v6(&self, multicast_loop_v6: bool) -> io::Result<()> {
+        self.0.state.lock().unwrap().multicast_loop_v6 = multicast_loop_v6;
+        Ok(())
     }
 
     pub fn multicast_loop_v6(&self) -> io::Result<bool> {
-        self.0
+        Ok(self.0.state.lock().unwrap().multicast_loop_v6)
     }
 
+    // TODO: multicast groups need a real network stack
     pub fn join_multicast_v4(&self, _: &Ipv4Addr, _: &Ipv4Addr) -> io::Result<()> {
-        self.0
+        unsupported()
     }
 
     pub fn join_multicast_v6(&self, _: &Ipv6Addr, _: u32) -> io::Result<()> {
-        self.0
+        unsupported()
     }
 
     pub fn leave_multicast_v4(&self, _: &Ipv4Addr, _: &Ipv4Addr) -> io::Result<()> {
-        self.0
+        unsupported()
     }
 
     pub fn leave_multicast_v6(&self, _: &Ipv6Addr, _: u32) -> io::Result<()> {
-        self.0
+        unsupported()
     }
 
-    pub fn set_ttl(&self, _: u32) -> io::Result<()> {
-        self.0
+    pub fn set_ttl(&self, ttl: u32) -> io::Result<()> {
+        self.0.state.lock().unwrap().ttl = ttl;
+        Ok(())
     }
 
     pub fn ttl(&self) -> io::Result<u32> {
-        self.0
+        Ok(self.0.state.lock().unwrap().ttl)
     }
 
     pub fn take_error(&self) -> io::Result<Option<io::Error>> {
-        self.0
+        Ok(None)
     }
 
-    pub fn set_nonblocking(&self, _: bool) -> io::Result<()> {
-        self.0
+    pub fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        self.0.state.lock().unwrap().nonblocking = nonblocking;
+        Ok(())
     }
 
-    pub fn recv(&self, _: &mut [u8]) -> io::Result<usize> {
-        self.0
+    pub fn recv(&self, buf: &mut [u8]) -> io::Result<usize> {
+        self.recv_from(buf).map(|(n, _)| n)
     }
 
-    pub fn peek(&self, _: &mut [u8]) -> io::Result<usize> {
-        self.0
+    pub fn peek(&self, buf: &mut [u8]) -> io::Result<usize> {
+        self.peek_from(buf).map(|(n, _)| n)
     }
 
-    pub fn send(&self, _: &[u8]) -> io::Result<usize> {
-        self.0
+    pub fn send(&self, buf: &[u8]) -> io::Result<usize> {
+        let peer = self.peer_addr()?;
+        self.send_to(buf, &peer)
     }
 
-    pub fn connect(&self, _: io::Result<&SocketAddr>) -> io::Result<()> {
-        self.0
+    pub fn connect(&self, addr: io::Result<&SocketAddr>) -> io::Result<()> {
+        let addr = addr?;
+        if !is_local(&addr.ip()) {
+            return Err(io::const_io_error!(
+                io::ErrorKind::NetworkUnreachable,
+                "only loopback addresses are reachable on this platform",
+            ));
+        }
+        self.0.state.lock().unwrap().peer = Some(SocketAddr::new(loopback(addr), addr.port()));
+        Ok(())
     }
 }
 
 impl fmt::Debug for UdpSocket {
-    fn fmt(&self, _f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        self.0
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UdpSocket").field("addr", &self.0.local).finish()
     }
 }
 
-pub struct LookupHost(!);
+////////////////////////////////////////////////////////////////////////////////
+// Name lookup
+////////////////////////////////////////////////////////////////////////////////
+
+pub struct LookupHost {
+    addrs: vec::IntoIter<SocketAddr>,
+    port: u16,
+}
 
 impl LookupHost {
     pub fn port(&self) -> u16 {
-        self.0
+        self.port
     }
 }
 
 impl Iterator for LookupHost {
     type Item = SocketAddr;
     fn next(&mut self) -> Option<SocketAddr> {
-        self.0
+        self.addrs.next()
     }
 }
 
 impl TryFrom<&str> for LookupHost {
     type Error = io::Error;
 
-    fn try_from(_v: &str) -> io::Result<LookupHost> {
-        todo!("LookupHost, TryFrom<&str>")
+    fn try_from(s: &str) -> io::Result<LookupHost> {
+        macro_rules! try_opt {
+            ($e:expr, $msg:expr) => {
+                match $e {
+                    Some(r) => r,
+                    None => return Err(io::const_io_error!(io::ErrorKind::InvalidInput, $msg)),
+                }
+            };
+        }
+
+        // split the string by ':' and convert the second part to u16
+        let (host, port_str) = try_opt!(s.rsplit_once(':'), "invalid socket address");
+        let port: u16 = try_opt!(port_str.parse().ok(), "invalid port value");
+        (host, port).try_into()
     }
 }
 
 impl<'a> TryFrom<(&'a str, u16)> for LookupHost {
     type Error = io::Error;
 
-    fn try_from(_v: (&'a str, u16)) -> io::Result<LookupHost> {
-        todo!("LookupHost, TryFrom<(&str, u16)>")
+    fn try_from((host, port): (&'a str, u16)) -> io::Result<LookupHost> {
+        // there is no resolver, only the loopback interface has a name
+        if !host.eq_ignore_ascii_case("localhost") {
+            return Err(io::const_io_error!(
+                io::ErrorKind::NotFound,
+                "failed to lookup address information, only `localhost` is known on this platform",
+            ));
+        }
+
+        let addrs = vec![
+            SocketAddr::new(Ipv4Addr::LOCALHOST.into(), port),
+            SocketAddr::new(Ipv6Addr::LOCALHOST.into(), port),
+        ];
+        Ok(LookupHost { addrs: addrs.into_iter(), port })
     }
 }
 
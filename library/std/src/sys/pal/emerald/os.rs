@@ -1,15 +1,23 @@
 use emerald_std::SyscallError;
 
+use crate::collections::BTreeMap;
 use crate::error::Error as StdError;
 use crate::ffi::{OsStr, OsString};
 use crate::fmt;
 use crate::io;
-use crate::marker::PhantomData;
-use crate::os::emerald::prelude::OsStringExt;
+use crate::iter;
+use crate::mem::ManuallyDrop;
+use crate::os::emerald::io::{AsFd, FromRawFd, RawFd};
+use crate::os::emerald::prelude::{OsStrExt, OsStringExt};
 use crate::path::{self, PathBuf};
 use crate::ptr::addr_of;
+use crate::slice;
+use crate::sync::Mutex;
 use crate::sys::common::small_c_string::run_path_with_cstr;
 use crate::sys::pal::emerald::syscall_to_io_error;
+use crate::vec;
+
+use super::fd::FileDesc;
 
 #[cfg(not(test))]
 #[cfg(feature = "panic_unwind")]
@@ -50,15 +58,20 @@ pub extern "C" fn _start(argc: isize, argv: *const *const u8) -> ! {
             .init(emerald_std::process::process_metadata().eh_frame_address);
         unwind::set_custom_eh_frame_finder(&*addr_of!(eh_unwinding::EH_FRAME_SETTINGS)).ok();
     }
-    exit(unsafe { main(argc, argv) });
+    let result = unsafe { main(argc, argv) };
+
+    // the main thread is exiting, run the destructors of its thread locals
+    unsafe { super::thread_local_key::run_dtors() };
+
+    exit(result);
 }
 
 pub fn errno() -> i32 {
-    0
+    super::last_error()
 }
 
-pub fn error_string(_errno: i32) -> String {
-    "operation successful".to_string()
+pub fn error_string(errno: i32) -> String {
+    super::error_message(errno)
 }
 
 pub fn getcwd() -> io::Result<PathBuf> {
@@ -93,40 +106,70 @@ pub fn chdir(p: &path::Path) -> io::Result<()> {
     })
 }
 
-pub struct SplitPaths<'a>(!, PhantomData<&'a ()>);
+const PATH_SEPARATOR: u8 = b':';
+
+pub struct SplitPaths<'a> {
+    iter: iter::Map<slice::Split<'a, u8, fn(&u8) -> bool>, fn(&'a [u8]) -> PathBuf>,
+}
 
-pub fn split_paths(_unparsed: &OsStr) -> SplitPaths<'_> {
-    todo!("split_paths")
+pub fn split_paths(unparsed: &OsStr) -> SplitPaths<'_> {
+    fn bytes_to_path(b: &[u8]) -> PathBuf {
+        PathBuf::from(<OsStr as OsStrExt>::from_bytes(b))
+    }
+    fn is_separator(b: &u8) -> bool {
+        *b == PATH_SEPARATOR
+    }
+    let unparsed = unparsed.as_bytes();
+    SplitPaths {
+        iter: unparsed
+            .split(is_separator as fn(&u8) -> bool)
+            .map(bytes_to_path as fn(&[u8]) -> PathBuf),
+    }
 }
 
 impl<'a> Iterator for SplitPaths<'a> {
     type Item = PathBuf;
     fn next(&mut self) -> Option<PathBuf> {
-        self.0
+        self.iter.next()
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
     }
 }
 
 #[derive(Debug)]
 pub struct JoinPathsError;
 
-pub fn join_paths<I, T>(_paths: I) -> Result<OsString, JoinPathsError>
+pub fn join_paths<I, T>(paths: I) -> Result<OsString, JoinPathsError>
 where
     I: Iterator<Item = T>,
     T: AsRef<OsStr>,
 {
-    Err(JoinPathsError)
+    let mut joined = Vec::new();
+
+    for (i, path) in paths.enumerate() {
+        let path = path.as_ref().as_bytes();
+        if i > 0 {
+            joined.push(PATH_SEPARATOR)
+        }
+        if path.contains(&PATH_SEPARATOR) {
+            return Err(JoinPathsError);
+        }
+        joined.extend_from_slice(path);
+    }
+    Ok(OsStringExt::from_vec(joined))
 }
 
 impl fmt::Display for JoinPathsError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        "not supported on this platform yet".fmt(f)
+        write!(f, "path segment contains separator `{}`", char::from(PATH_SEPARATOR))
     }
 }
 
 impl StdError for JoinPathsError {
     #[allow(deprecated)]
     fn description(&self) -> &str {
-        "not supported on this platform yet"
+        "failed to join paths"
     }
 }
 
@@ -146,56 +189,138 @@ pub fn current_exe() -> io::Result<PathBuf> {
     if !path.has_root() { getcwd().map(|cwd| cwd.join(path)) } else { Ok(path) }
 }
 
-pub struct Env(!);
+// The kernel doesn't give processes an environment block, so it's only kept here
+// for the lifetime of the process. It starts with what the parent sent through
+// `ENV_FD` if it was spawned by std, and is empty otherwise.
+static ENV: Mutex<BTreeMap<OsString, OsString>> = Mutex::new(BTreeMap::new());
+
+/// The fd a child process reads its environment from, see `Command::spawn`.
+///
+/// The spawn syscall can't pass an environment, so the parent maps the read end of
+/// a pipe to this fd, and writes `ENV_MAGIC` followed by `key=value\0` entries to it.
+pub const ENV_FD: RawFd = 3;
+pub const ENV_MAGIC: &[u8] = b"EMERALD_ENV\0";
+
+/// One-time global initialization.
+pub fn init_environment() {
+    let Some(block) = read_env_block() else {
+        return;
+    };
+
+    let mut env = ENV.lock().unwrap();
+    for var in block.split(|&b| b == 0).filter(|var| !var.is_empty()) {
+        // keys can't contain `=`, so the first one is the separator
+        if let Some(pos) = var.iter().position(|&b| b == b'=') {
+            env.insert(
+                OsString::from_vec(var[..pos].to_vec()),
+                OsString::from_vec(var[pos + 1..].to_vec()),
+            );
+        }
+    }
+}
+
+fn read_env_block() -> Option<Vec<u8>> {
+    // Safety: the fd is only closed below if it's the environment pipe from our parent,
+    //         which nothing else in the process knows about
+    let fd = ManuallyDrop::new(unsafe { FileDesc::from_raw_fd(ENV_FD) });
+
+    // the fd is not open if we were not spawned by std, and don't wait for input
+    // from a terminal that happens to be here
+    if super::io::try_is_terminal(fd.as_fd()).unwrap_or(true) {
+        return None;
+    }
+    fd.set_nonblocking(false).ok()?;
+
+    let mut block = Vec::new();
+    let mut buf = [0; 1024];
+    loop {
+        match fd.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => block.extend_from_slice(&buf[..n]),
+            // the parent closed its end of the pipe
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(_) => return None,
+        }
+    }
+
+    // some other file, leave it for whoever mapped it
+    let block = block.strip_prefix(ENV_MAGIC)?.to_vec();
+    drop(ManuallyDrop::into_inner(fd));
+    Some(block)
+}
+
+pub struct Env {
+    iter: vec::IntoIter<(OsString, OsString)>,
+}
+
+// FIXME(https://github.com/rust-lang/rust/issues/114583): Remove this when <OsStr as Debug>::fmt matches <str as Debug>::fmt.
+pub struct EnvStrDebug<'a> {
+    slice: &'a [(OsString, OsString)],
+}
+
+impl fmt::Debug for EnvStrDebug<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self { slice } = self;
+        f.debug_list()
+            .entries(slice.iter().map(|(a, b)| (a.to_str().unwrap(), b.to_str().unwrap())))
+            .finish()
+    }
+}
 
 impl Env {
-    // FIXME(https://github.com/rust-lang/rust/issues/114583): Remove this when <OsStr as Debug>::fmt matches <str as Debug>::fmt.
     pub fn str_debug(&self) -> impl fmt::Debug + '_ {
-        let Self(inner) = self;
-        match *inner {}
+        let Self { iter } = self;
+        EnvStrDebug { slice: iter.as_slice() }
     }
 }
 
 impl fmt::Debug for Env {
-    fn fmt(&self, _: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let Self(inner) = self;
-        match *inner {}
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self { iter } = self;
+        f.debug_list().entries(iter.as_slice()).finish()
     }
 }
 
+impl !Send for Env {}
+impl !Sync for Env {}
+
 impl Iterator for Env {
     type Item = (OsString, OsString);
     fn next(&mut self) -> Option<(OsString, OsString)> {
-        let Self(inner) = self;
-        match *inner {}
+        self.iter.next()
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
     }
 }
 
 pub fn env() -> Env {
-    panic!("not supported on this platform")
+    let env = ENV.lock().unwrap();
+    let result = env.iter().map(|(k, v)| (k.clone(), v.clone())).collect::<Vec<_>>();
+    Env { iter: result.into_iter() }
 }
 
-pub fn getenv(str: &OsStr) -> Option<OsString> {
-    if str == "RUST_BACKTRACE" {
-        return Some("full".into());
-    }
-    None
+pub fn getenv(k: &OsStr) -> Option<OsString> {
+    ENV.lock().unwrap().get(k).cloned()
 }
 
-pub fn setenv(_: &OsStr, _: &OsStr) -> io::Result<()> {
-    Err(io::const_io_error!(io::ErrorKind::Unsupported, "cannot set env vars on this platform"))
+pub fn setenv(k: &OsStr, v: &OsStr) -> io::Result<()> {
+    ENV.lock().unwrap().insert(k.to_owned(), v.to_owned());
+    Ok(())
 }
 
-pub fn unsetenv(_: &OsStr) -> io::Result<()> {
-    Err(io::const_io_error!(io::ErrorKind::Unsupported, "cannot unset env vars on this platform"))
+pub fn unsetenv(k: &OsStr) -> io::Result<()> {
+    ENV.lock().unwrap().remove(k);
+    Ok(())
 }
 
 pub fn temp_dir() -> PathBuf {
-    panic!("no filesystem on this platform")
+    getenv(OsStr::new("TMPDIR")).map(PathBuf::from).unwrap_or_else(|| PathBuf::from("/tmp"))
 }
 
 pub fn home_dir() -> Option<PathBuf> {
-    None
+    // There are no users, so everyone shares the root directory by default
+    getenv(OsStr::new("HOME")).map(PathBuf::from).or_else(|| Some(PathBuf::from("/")))
 }
 
 pub fn exit(code: i32) -> ! {
@@ -203,5 +328,5 @@ pub fn exit(code: i32) -> ! {
 }
 
 pub fn getpid() -> u32 {
-    panic!("no pids on this platform")
+    emerald_std::process::process_metadata().pid as u32
 }
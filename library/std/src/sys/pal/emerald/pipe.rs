@@ -3,9 +3,10 @@
 use crate::{
     io::{self, BorrowedCursor, IoSlice, IoSliceMut},
     os::emerald::io::FromRawFd,
+    time::Duration,
 };
 
-use super::{fd::FileDesc, syscall_to_io_error};
+use super::{fd::FileDesc, syscall_to_io_error, thread::Thread};
 
 pub struct AnonPipe(FileDesc);
 
@@ -15,9 +16,8 @@ pub fn anon_pipe() -> io::Result<(AnonPipe, AnonPipe)> {
     let reader = unsafe { FileDesc::from_raw_fd(reader) };
     let writer = unsafe { FileDesc::from_raw_fd(writer) };
 
-    // TODO: set cloexec
-    // reader.set_cloexec()?;
-    // writer.set_cloexec()?;
+    reader.set_cloexec()?;
+    writer.set_cloexec()?;
 
     Ok((AnonPipe(reader), AnonPipe(writer)))
 }
@@ -60,6 +60,54 @@ pub fn is_write_vectored(&self) -> bool {
     }
 }
 
-pub fn read2(_p1: AnonPipe, _v1: &mut Vec<u8>, _p2: AnonPipe, _v2: &mut Vec<u8>) -> io::Result<()> {
-    todo!()
+pub fn read2(p1: AnonPipe, v1: &mut Vec<u8>, p2: AnonPipe, v2: &mut Vec<u8>) -> io::Result<()> {
+    // TODO: there is no `poll` syscall yet, so set both pipes into non-blocking mode
+    //       and read from them in turns, that way the child filling one pipe
+    //       doesn't block us waiting on the other one.
+
+    // Read what is available in the pipe, in non-blocking mode `Ok(0)` means that
+    // there is no data yet, and the end of the pipe is reported as `EndOfFile`
+    // once the writer is closed, which we return as `None`.
+    fn read(fd: &FileDesc, dst: &mut Vec<u8>) -> io::Result<Option<usize>> {
+        let mut buf = [0; 1024];
+        match fd.read(&mut buf) {
+            Ok(n) => {
+                dst.extend_from_slice(&buf[..n]);
+                Ok(Some(n))
+            }
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    let p1 = p1.into_inner();
+    let p2 = p2.into_inner();
+    p1.set_nonblocking(true)?;
+    p2.set_nonblocking(true)?;
+
+    let mut p1_open = true;
+    let mut p2_open = true;
+    while p1_open || p2_open {
+        let mut got_data = false;
+
+        if p1_open {
+            match read(&p1, v1)? {
+                Some(n) => got_data |= n != 0,
+                None => p1_open = false,
+            }
+        }
+        if p2_open {
+            match read(&p2, v2)? {
+                Some(n) => got_data |= n != 0,
+                None => p2_open = false,
+            }
+        }
+
+        // don't keep the CPU busy while the child is working
+        if !got_data && (p1_open || p2_open) {
+            Thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    Ok(())
 }
@@ -26,6 +26,11 @@
 
 use super::fd::FileDesc;
 use super::pipe;
+use super::unsupported;
+
+/// The directories searched for programs when `PATH` is not set, the programs that come
+/// with Emerald are all installed in the root directory.
+const DEFAULT_PATH: &str = "/";
 
 struct Argv(Vec<*const c_char>);
 
@@ -43,7 +48,8 @@ pub struct Command {
     stdout: Option<Stdio>,
     stderr: Option<Stdio>,
 
-    spawned: bool,
+    cwd: Option<CString>,
+    saw_nul: bool,
 }
 
 // passed back to std::process with the pipes connected to the child, if any
@@ -59,6 +65,10 @@ pub enum Stdio {
     Null,
     MakePipe,
     Fd(FileDesc),
+    // one of our stdio fds, `FD_STDIN`, `FD_STDOUT` or `FD_STDERR`
+    ParentStdio(RawFd),
+    // the fd was moved to a previous child, see `to_child_stdio`
+    Moved,
 }
 
 // used to configure file mappings for the child
@@ -86,7 +96,8 @@ pub fn into_file_mappings(self) -> Option<SpawnFileMapping> {
 
 impl Command {
     pub fn new(program: &OsStr) -> Command {
-        let program = CString::new(program.as_encoded_bytes()).unwrap();
+        let mut saw_nul = false;
+        let program = os2c(program, &mut saw_nul);
         Command {
             program: program.clone(),
             argv: Argv(vec![program.as_ptr(), ptr::null()]),
@@ -96,14 +107,15 @@ pub fn new(program: &OsStr) -> Command {
             stdout: None,
             stderr: None,
 
-            spawned: false,
+            cwd: None,
+            saw_nul,
         }
     }
 
     pub fn arg(&mut self, arg: &OsStr) {
         // Overwrite the trailing null pointer in `argv` and then add a new null
         // pointer.
-        let arg = CString::new(arg.as_encoded_bytes()).unwrap();
+        let arg = os2c(arg, &mut self.saw_nul);
         self.argv.0[self.args.len()] = arg.as_ptr();
         self.argv.0.push(ptr::null());
 
@@ -116,7 +128,9 @@ pub fn env_mut(&mut self) -> &mut CommandEnv {
         &mut self.env
     }
 
-    pub fn cwd(&mut self, _dir: &OsStr) {}
+    pub fn cwd(&mut self, dir: &OsStr) {
+        self.cwd = Some(os2c(dir, &mut self.saw_nul));
+    }
 
     pub fn stdin(&mut self, stdin: Stdio) {
         self.stdin = Some(stdin);
@@ -154,7 +168,10 @@ fn get_argv(&self) -> &Vec<*const c_char> {
     }
 
     pub fn get_current_dir(&self) -> Option<&Path> {
-        None
+        // Safety: we have used `as_encoded_bytes` to create this `CString`, so this is valid
+        self.cwd
+            .as_ref()
+            .map(|cs| Path::new(unsafe { OsStr::from_encoded_bytes_unchecked(cs.as_bytes()) }))
     }
 
     fn setup_io(
@@ -165,13 +182,13 @@ fn setup_io(
         let mut null = Stdio::Null;
         let default_stdin = if needs_stdin { &mut default } else { &mut null };
         let stdin = self.stdin.as_mut().unwrap_or(default_stdin);
-        let (their_stdin, our_stdin) = stdin.to_child_stdio(true)?;
+        let (their_stdin, our_stdin) = stdin.to_child_stdio(FD_STDIN)?;
 
         let stdout = self.stdout.as_mut().unwrap_or(&mut default);
-        let (their_stdout, our_stdout) = stdout.to_child_stdio(false)?;
+        let (their_stdout, our_stdout) = stdout.to_child_stdio(FD_STDOUT)?;
 
         let stderr = self.stderr.as_mut().unwrap_or(&mut default);
-        let (their_stderr, our_stderr) = stderr.to_child_stdio(false)?;
+        let (their_stderr, our_stderr) = stderr.to_child_stdio(FD_STDERR)?;
         let ours = StdioPipes { stdin: our_stdin, stdout: our_stdout, stderr: our_stderr };
         let theirs = ChildPipes { stdin: their_stdin, stdout: their_stdout, stderr: their_stderr };
         Ok((ours, theirs))
@@ -182,20 +199,28 @@ pub fn spawn(
         default: Stdio,
         needs_stdin: bool,
     ) -> io::Result<(Process, StdioPipes)> {
-        // TODO: add support for multiple spawns.
-        //       this requires `dups` or `clone` for files.
-        //       the reason its like that is that `Stdio::Fd` must be moved
-        //       so we need a way to clone the file
-        if self.spawned {
-            return Err(io::Error::new(io::ErrorKind::Other, "Command can only be spawned once"));
+        if self.saw_nul {
+            return Err(io::const_io_error!(
+                io::ErrorKind::InvalidInput,
+                "nul byte found in provided data",
+            ));
         }
 
+        let env_block = self.env_block()?;
+
         let (ours, theirs) = self.setup_io(default, needs_stdin)?;
-        self.spawned = true;
 
-        // setup 3 mappings as the max, and only use what's needed
-        let mut file_mappings = [SpawnFileMapping { src_fd: 0, dst_fd: 0 }; 3];
-        let mut mappings_i = 0;
+        // TODO: the spawn syscall can't pass an environment, so the child reads it from a
+        //       pipe at `ENV_FD` when it starts, see `os::init_environment`
+        let (env_reader, env_writer) = pipe::anon_pipe()?;
+
+        // setup 4 mappings as the max (stdio and the environment), and only use what's needed
+        let mut file_mappings = [SpawnFileMapping { src_fd: 0, dst_fd: 0 }; 4];
+        file_mappings[0] = SpawnFileMapping {
+            src_fd: env_reader.into_inner().into_raw_fd(),
+            dst_fd: super::os::ENV_FD,
+        };
+        let mut mappings_i = 1;
 
         if let Some(mut file_map) = theirs.stdin.into_file_mappings() {
             file_map.dst_fd = FD_STDIN;
@@ -213,15 +238,94 @@ pub fn spawn(
             mappings_i += 1;
         }
 
-        let pid = unsafe {
+        // TODO: the spawn syscall doesn't take a working directory, so we switch to it
+        //       for the duration of the spawn, and the child inherits it.
+        //       This is fine since we only have one thread (see `Thread::new`).
+        let parent_cwd = match &self.cwd {
+            Some(cwd) => {
+                let parent_cwd = super::os::getcwd()?;
+                unsafe { emerald_std::io::syscall_chdir(cwd).map_err(syscall_to_io_error)? };
+                Some(parent_cwd)
+            }
+            None => None,
+        };
+
+        let pid = self.resolve_program().and_then(|program| unsafe {
             emerald_std::process::spawn(
-                self.get_program_cstr(),
+                program.as_deref().unwrap_or(self.get_program_cstr()),
                 self.get_argv(),
                 &file_mappings[..mappings_i],
             )
-            .map_err(syscall_to_io_error)?
-        };
-        Ok((Process { pid: pid as u32 }, ours))
+            .map_err(syscall_to_io_error)
+        });
+
+        // go back to our directory even if the spawn failed
+        if let Some(parent_cwd) = parent_cwd {
+            if let Err(e) = super::os::chdir(&parent_cwd) {
+                // the child is already running, and returning an error would lose it,
+                // but continuing in the wrong directory would silently break relative paths
+                if pid.is_ok() {
+                    rtabort!("failed to restore the working directory after spawning: {e}");
+                }
+                return Err(e);
+            }
+        }
+
+        let pid = pid?;
+        write_env_block(env_writer, &env_block);
+
+        Ok((Process { pid: pid as u32, status: None }, ours))
+    }
+
+    /// The environment of the child, in the format `os::init_environment` reads.
+    fn env_block(&self) -> io::Result<Vec<u8>> {
+        let mut block = super::os::ENV_MAGIC.to_vec();
+        for (key, value) in self.env.capture() {
+            let (key, value) = (key.as_encoded_bytes(), value.as_encoded_bytes());
+            if key.contains(&b'=') || key.contains(&0) || value.contains(&0) {
+                return Err(io::const_io_error!(
+                    io::ErrorKind::InvalidInput,
+                    "environment variable names can't contain `=`, and variables can't contain nul bytes",
+                ));
+            }
+            block.extend_from_slice(key);
+            block.push(b'=');
+            block.extend_from_slice(value);
+            block.push(0);
+        }
+        Ok(block)
+    }
+
+    /// Searches for the program in the directories of `PATH`, like `execvp` does on unix.
+    ///
+    /// Returns `None` if the program should be used as is, which is the case when it
+    /// contains a `/`. If `PATH` is not set, `DEFAULT_PATH` is searched instead.
+    fn resolve_program(&self) -> io::Result<Option<CString>> {
+        let program = self.program.as_bytes();
+        if program.contains(&b'/') {
+            return Ok(None);
+        }
+        let paths = super::os::getenv(OsStr::new("PATH"))
+            .unwrap_or_else(|| OsStr::new(DEFAULT_PATH).to_owned());
+
+        for dir in super::os::split_paths(&paths) {
+            let candidate = dir.join(self.get_program());
+            match super::fs::stat(&candidate) {
+                Ok(attr) if attr.file_type().is_file() => {
+                    return CString::new(candidate.into_os_string().into_encoded_bytes())
+                        .map(Some)
+                        .map_err(|_| {
+                            io::const_io_error!(
+                                io::ErrorKind::InvalidInput,
+                                "program path contains an interior nul byte",
+                            )
+                        });
+                }
+                _ => {}
+            }
+        }
+
+        Err(io::const_io_error!(io::ErrorKind::NotFound, "program not found in PATH"))
     }
 
     pub fn output(&mut self) -> io::Result<(ExitStatus, Vec<u8>, Vec<u8>)> {
@@ -230,8 +334,29 @@ pub fn output(&mut self) -> io::Result<(ExitStatus, Vec<u8>, Vec<u8>)> {
     }
 }
 
+// The child is already running, so failing to give it its environment is not an error,
+// it either exited already, or isn't using std and never reads it.
+// TODO: this blocks if the block doesn't fit in the pipe and the child never reads it
+fn write_env_block(pipe: AnonPipe, mut block: &[u8]) {
+    while !block.is_empty() {
+        match pipe.write(block) {
+            Ok(0) | Err(_) => break,
+            Ok(n) => block = &block[n..],
+        }
+    }
+}
+
+fn os2c(s: &OsStr, saw_nul: &mut bool) -> CString {
+    CString::new(s.as_encoded_bytes()).unwrap_or_else(|_e| {
+        *saw_nul = true;
+        CString::new("<string-with-nul>").unwrap()
+    })
+}
+
 impl Stdio {
-    pub fn to_child_stdio(&mut self, readable: bool) -> io::Result<(ChildStdio, Option<AnonPipe>)> {
+    pub fn to_child_stdio(&mut self, dst_fd: RawFd) -> io::Result<(ChildStdio, Option<AnonPipe>)> {
+        let readable = dst_fd == FD_STDIN;
+
         match self {
             Stdio::Inherit => Ok((ChildStdio::Inherit, None)),
 
@@ -243,9 +368,9 @@ pub fn to_child_stdio(&mut self, readable: bool) -> io::Result<(ChildStdio, Opti
             // stderr. No matter which we dup first, the second will get
             // overwritten prematurely.
             Stdio::Fd(_) => {
-                // replace self with inherit
-                // TODO: this is not good, replace with `dups` or cloning the file somehow
-                let fd = core::mem::replace(self, Stdio::Inherit);
+                // TODO: we can't duplicate fds yet (see `FileDesc::duplicate`), so the fd is moved
+                //       to the first child, and spawning with it again will fail
+                let fd = core::mem::replace(self, Stdio::Moved);
 
                 let fd = match fd {
                     Stdio::Fd(fd) => fd,
@@ -270,6 +395,19 @@ pub fn to_child_stdio(&mut self, readable: bool) -> io::Result<(ChildStdio, Opti
                 Ok((ChildStdio::Owned(theirs.into_inner()), Some(ours)))
             }
 
+            Stdio::Moved => Err(io::const_io_error!(
+                io::ErrorKind::Unsupported,
+                "the fd was already moved to a previous child process, duplicating fds is not supported on this platform yet",
+            )),
+
+            // giving our stdio fd to the same stdio of the child is the same as inheriting it
+            Stdio::ParentStdio(fd) if *fd == dst_fd => Ok((ChildStdio::Inherit, None)),
+            // TODO: this requires duplicating the fd, as the child takes ownership of it
+            Stdio::ParentStdio(_) => Err(io::const_io_error!(
+                io::ErrorKind::Unsupported,
+                "forwarding a stdio fd to a different stdio of the child process is not supported on this platform yet",
+            )),
+
             // TODO: replace with null device
             Stdio::Null => Ok((ChildStdio::Inherit, None)),
         }
@@ -284,17 +422,13 @@ fn from(pipe: AnonPipe) -> Stdio {
 
 impl From<io::Stdout> for Stdio {
     fn from(_: io::Stdout) -> Stdio {
-        // FIXME: This is wrong.
-        // Instead, the Stdio we have here should be a unit struct.
-        panic!("unsupported")
+        Stdio::ParentStdio(FD_STDOUT)
     }
 }
 
 impl From<io::Stderr> for Stdio {
     fn from(_: io::Stderr) -> Stdio {
-        // FIXME: This is wrong.
-        // Instead, the Stdio we have here should be a unit struct.
-        panic!("unsupported")
+        Stdio::ParentStdio(FD_STDERR)
     }
 }
 
@@ -361,34 +495,28 @@ pub fn code(self) -> Option<NonZeroI32> {
 }
 
 #[derive(PartialEq, Eq, Clone, Copy, Debug)]
-pub enum ExitCode {
-    Success,
-    Failure,
-}
+pub struct ExitCode(u8);
 
 impl ExitCode {
-    pub const SUCCESS: ExitCode = ExitCode::Success;
-    pub const FAILURE: ExitCode = ExitCode::Failure;
+    pub const SUCCESS: ExitCode = ExitCode(0);
+    pub const FAILURE: ExitCode = ExitCode(1);
 
     pub fn as_i32(&self) -> i32 {
-        match *self {
-            ExitCode::Success => 0,
-            ExitCode::Failure => 1,
-        }
+        self.0 as i32
     }
 }
 
 impl From<u8> for ExitCode {
     fn from(code: u8) -> Self {
-        match code {
-            0 => Self::Success,
-            1..=255 => Self::Failure,
-        }
+        Self(code)
     }
 }
 
 pub struct Process {
     pid: u32,
+    // the kernel forgets about the process once we get its exit code,
+    // so keep it here to be able to return it again
+    status: Option<ExitStatus>,
 }
 
 impl Process {
@@ -397,21 +525,40 @@ pub fn id(&self) -> u32 {
     }
 
     pub fn kill(&mut self) -> io::Result<()> {
-        todo!()
+        // If we've already waited on this process then the pid can be recycled
+        // and used for another process, and we probably shouldn't be killing
+        // random processes, so return Ok because the process has exited already.
+        if self.try_wait()?.is_some() {
+            return Ok(());
+        }
+        // TODO: there is no syscall to kill a process yet
+        unsupported()
     }
 
     pub fn wait(&mut self) -> io::Result<ExitStatus> {
+        if let Some(status) = self.status {
+            return Ok(status);
+        }
         let status_code = unsafe {
             emerald_std::process::wait_for_pid(self.pid as u64, true)
                 .map_err(syscall_to_io_error)?
         };
-        Ok(ExitStatus(status_code as i32))
+        let status = ExitStatus(status_code as i32);
+        self.status = Some(status);
+        Ok(status)
     }
 
     pub fn try_wait(&mut self) -> io::Result<Option<ExitStatus>> {
+        if let Some(status) = self.status {
+            return Ok(Some(status));
+        }
         let status_code = unsafe { emerald_std::process::wait_for_pid(self.pid as u64, false) };
         match status_code {
-            Ok(status_code) => Ok(Some(ExitStatus(status_code as i32))),
+            Ok(status_code) => {
+                let status = ExitStatus(status_code as i32);
+                self.status = Some(status);
+                Ok(Some(status))
+            }
             Err(SyscallError::ProcessStillRunning) => Ok(None),
             Err(e) => Err(syscall_to_io_error(e)),
         }
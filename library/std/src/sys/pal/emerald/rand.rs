@@ -0,0 +1,79 @@
+use core::arch::x86_64::{__cpuid, _rdrand64_step, _rdtsc};
+
+use crate::io;
+use crate::sync::atomic::{AtomicU64, Ordering};
+use emerald_std::clock::ClockType;
+
+// TODO: use a kernel RNG once there is a syscall for it, for now use
+//       the CPU's `rdrand` if available.
+pub fn random_u64() -> u64 {
+    hardware_u64().unwrap_or_else(fallback_u64)
+}
+
+/// Fills `buf` from the hardware generator only, see `os::emerald::rand::fill_bytes`.
+pub fn fill_bytes(buf: &mut [u8]) -> io::Result<()> {
+    for chunk in buf.chunks_mut(8) {
+        let value = hardware_u64().ok_or(io::const_io_error!(
+            io::ErrorKind::Unsupported,
+            "no hardware random number generator is available",
+        ))?;
+        chunk.copy_from_slice(&value.to_ne_bytes()[..chunk.len()]);
+    }
+    Ok(())
+}
+
+fn hardware_u64() -> Option<u64> {
+    if !has_rdrand() {
+        return None;
+    }
+    // Safety: we just checked that the CPU supports `rdrand`
+    unsafe { rdrand64() }
+}
+
+fn has_rdrand() -> bool {
+    // CPUID.01H:ECX.RDRAND[bit 30]
+    // Safety: `cpuid` is available on all x86_64 CPUs
+    unsafe { __cpuid(1).ecx & (1 << 30) != 0 }
+}
+
+#[target_feature(enable = "rdrand")]
+unsafe fn rdrand64() -> Option<u64> {
+    let mut ret = 0;
+    // `rdrand` can fail if the entropy source is exhausted,
+    // retry a couple of times as recommended by Intel
+    for _ in 0..10 {
+        if unsafe { _rdrand64_step(&mut ret) } == 1 {
+            return Some(ret);
+        }
+    }
+    None
+}
+
+/// NOT SECURE: the inputs are the time, the cycle counter, the pid and a counter, which
+/// are all guessable, so the output is predictable. It only makes sure that values are
+/// not the same for every process, and that every call produces a different value.
+fn fallback_u64() -> u64 {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    let time = unsafe { emerald_std::clock::get_time(ClockType::RealTime) }
+        .map(|time| time.seconds ^ ((time.nanoseconds as u64) << 32))
+        .unwrap_or(0);
+    let tsc = cycle_counter();
+    let pid = emerald_std::process::process_metadata().pid;
+    let counter = COUNTER.fetch_add(1, Ordering::Relaxed);
+
+    splitmix64(time ^ tsc.rotate_left(17) ^ pid.rotate_left(41) ^ counter)
+}
+
+fn cycle_counter() -> u64 {
+    // Safety: `rdtsc` is available on all x86_64 CPUs
+    unsafe { _rdtsc() }
+}
+
+// mixes the bits of `x`, from https://prng.di.unimi.it/splitmix64.c
+fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
@@ -3,6 +3,8 @@
 use crate::num::NonZeroUsize;
 use crate::time::Duration;
 
+use super::unsupported;
+
 pub struct Thread(!);
 
 pub const DEFAULT_MIN_STACK_SIZE: usize = 4096;
@@ -10,7 +12,11 @@
 impl Thread {
     // unsafe: see thread::Builder::spawn_unchecked for safety requirements
     pub unsafe fn new(_stack: usize, _p: Box<dyn FnOnce()>) -> io::Result<Thread> {
-        todo!("Thread::new")
+        // TODO: the kernel can only create processes for now, once there is a syscall
+        //       to create a thread in the current address space, it should be used here.
+        //       Until then, return an error so that `thread::Builder::spawn` can be handled
+        //       by the caller instead of crashing the program.
+        unsupported()
     }
 
     pub fn yield_now() {
@@ -38,5 +44,7 @@ pub fn join(self) {
 }
 
 pub fn available_parallelism() -> io::Result<NonZeroUsize> {
-    todo!("available_parallelism")
+    // A process can only run on a single thread for now (see `Thread::new`),
+    // so anything more than `1` would just make thread pools fail to spawn their workers.
+    Ok(NonZeroUsize::MIN)
 }
@@ -1,36 +1,134 @@
-use core::sync::atomic::{AtomicUsize, Ordering};
+use crate::cell::UnsafeCell;
+use crate::mem::ManuallyDrop;
+use crate::ptr;
+use crate::sync::atomic::Ordering::{Acquire, Relaxed, Release};
+use crate::sync::atomic::{AtomicPtr, AtomicUsize};
 
+/// Thread Local Storage
+///
+/// Emerald doesn't setup a TLS block for processes, so instead each thread
+/// has a table of pointers indexed by `key`. The keys are shared between all
+/// threads, and the table grows as new keys are set.
+///
+/// A process only has the main thread for now (see `Thread::new`), so its
+/// table is the only one we need to keep.
 pub type Key = usize;
 
-// FIXME: this is only for 1 single thread at a time
-static mut MAP: [*mut u8; 256] = [core::ptr::null_mut(); 256];
-static MAP_INDEX: AtomicUsize = AtomicUsize::new(0);
+pub type Dtor = unsafe extern "C" fn(*mut u8);
+
+/// TLS keys start at `1`, `0` is used by `StaticKey` as a sentinel value.
+static TLS_KEY_INDEX: AtomicUsize = AtomicUsize::new(1);
+
+static DTORS: AtomicPtr<Node> = AtomicPtr::new(ptr::null_mut());
+
+struct Table(UnsafeCell<Vec<*mut u8>>);
+
+// Safety: there is only one thread, see `Thread::new`, this has to become a table per thread
+//         (and stop being `Sync`) once the kernel can create threads
+unsafe impl Sync for Table {}
+
+static MAIN_THREAD_TABLE: Table = Table(UnsafeCell::new(Vec::new()));
+
+fn with_table<R>(f: impl FnOnce(&mut Vec<*mut u8>) -> R) -> R {
+    // Safety: there is only one thread, and `f` never calls back into this module (the
+    //         destructors are called by `run_dtors` outside of it), so the borrow is unique
+    f(unsafe { &mut *MAIN_THREAD_TABLE.0.get() })
+}
 
 #[inline]
-pub unsafe fn create(_dtor: Option<unsafe extern "C" fn(*mut u8)>) -> Key {
-    // panic!("should not be used on this target");
-    let index = MAP_INDEX.fetch_add(1, Ordering::SeqCst);
-    index
+pub unsafe fn create(dtor: Option<Dtor>) -> Key {
+    // Allocate a new TLS key. These keys are shared among all threads.
+    let key = TLS_KEY_INDEX.fetch_add(1, Relaxed);
+    if let Some(f) = dtor {
+        unsafe { register_dtor(key, f) };
+    }
+    key
 }
 
 #[inline]
 pub unsafe fn set(key: Key, value: *mut u8) {
-    // panic!("should not be used on this target");
-    unsafe {
-        MAP[key] = value;
-    }
+    with_table(|table| {
+        if key >= table.len() {
+            // no need to grow the table for nothing, unset keys are null anyway
+            if value.is_null() {
+                return;
+            }
+            table.resize(key + 1, ptr::null_mut());
+        }
+        table[key] = value;
+    })
 }
 
 #[inline]
 pub unsafe fn get(key: Key) -> *mut u8 {
-    // panic!("should not be used on this target");
-    unsafe { MAP[key] }
+    with_table(|table| table.get(key).copied().unwrap_or(ptr::null_mut()))
 }
 
 #[inline]
-pub unsafe fn destroy(key: Key) {
-    // panic!("should not be used on this target");
-    unsafe {
-        MAP[key] = core::ptr::null_mut();
+pub unsafe fn destroy(_key: Key) {
+    // Just leak the key, the same as `xous`, keys are not reused, and
+    // the destructor stays registered since it might be running in another
+    // thread.
+}
+
+// -------------------------------------------------------------------------
+// Dtor registration
+//
+// Emerald has no native support for running destructors, so we keep a list
+// of the keys that have destructors, and run them when the thread exits
+// (currently only when `main` returns, see `os::_start`).
+//
+// Unregistration is not supported, keys are never reused, and a destructor
+// is only called when the value of its key is not null.
+
+struct Node {
+    dtor: Dtor,
+    key: Key,
+    next: *mut Node,
+}
+
+unsafe fn register_dtor(key: Key, dtor: Dtor) {
+    let mut node = ManuallyDrop::new(Box::new(Node { key, dtor, next: ptr::null_mut() }));
+
+    let mut head = DTORS.load(Acquire);
+    loop {
+        node.next = head;
+        match DTORS.compare_exchange(head, &mut **node, Release, Acquire) {
+            Ok(_) => return, // nothing to drop, we successfully added the node to the list
+            Err(cur) => head = cur,
+        }
+    }
+}
+
+/// Runs the destructors of all the keys set in the current thread.
+///
+/// # Safety
+/// Must only be called when the current thread is exiting, no TLS value
+/// should be used after this.
+pub unsafe fn run_dtors() {
+    let mut any_run = true;
+
+    // Run the destructor "some" number of times. This is 5x on Windows,
+    // so we copy it here. This allows TLS variables to create new
+    // TLS variables upon destruction that will also get destroyed.
+    // Keep going until we run out of tries or until we have nothing
+    // left to destroy.
+    for _ in 0..5 {
+        if !any_run {
+            break;
+        }
+        any_run = false;
+        let mut cur = DTORS.load(Acquire);
+        while !cur.is_null() {
+            let ptr = unsafe { get((*cur).key) };
+
+            if !ptr.is_null() {
+                unsafe { set((*cur).key, ptr::null_mut()) };
+                unsafe { ((*cur).dtor)(ptr as *mut _) };
+                any_run = true;
+            }
+
+            unsafe { cur = (*cur).next };
+        }
     }
 }
@@ -9,6 +9,7 @@
         target_os = "fuchsia",
         all(target_family = "wasm", target_feature = "atomics"),
         target_os = "hermit",
+        target_os = "emerald",
     ))] {
         mod futex;
         pub use futex::RwLock;
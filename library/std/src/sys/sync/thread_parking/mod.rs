@@ -8,6 +8,7 @@
         target_os = "dragonfly",
         target_os = "fuchsia",
         target_os = "hermit",
+        target_os = "emerald",
     ))] {
         mod futex;
         pub use futex::Parker;
@@ -36,6 +36,8 @@ pub struct Finder {
     "aarch64-apple-visionos",
     "aarch64-apple-visionos-sim",
     "x86_64-unknown-emerald",
+    "aarch64-unknown-emerald",
+    "riscv64gc-unknown-emerald",
 ];
 
 impl Finder {
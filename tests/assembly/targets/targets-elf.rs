@@ -21,6 +21,9 @@
 //@ revisions: aarch64_nintendo_switch_freestanding
 //@ [aarch64_nintendo_switch_freestanding] compile-flags: --target aarch64-nintendo-switch-freestanding
 //@ [aarch64_nintendo_switch_freestanding] needs-llvm-components: aarch64
+//@ revisions: aarch64_unknown_emerald
+//@ [aarch64_unknown_emerald] compile-flags: --target aarch64-unknown-emerald
+//@ [aarch64_unknown_emerald] needs-llvm-components: aarch64
 //@ revisions: aarch64_unknown_freebsd
 //@ [aarch64_unknown_freebsd] compile-flags: --target aarch64-unknown-freebsd
 //@ [aarch64_unknown_freebsd] needs-llvm-components: aarch64
@@ -396,6 +399,9 @@
 //@ revisions: riscv64_linux_android
 //@ [riscv64_linux_android] compile-flags: --target riscv64-linux-android
 //@ [riscv64_linux_android] needs-llvm-components: riscv
+//@ revisions: riscv64gc_unknown_emerald
+//@ [riscv64gc_unknown_emerald] compile-flags: --target riscv64gc-unknown-emerald
+//@ [riscv64gc_unknown_emerald] needs-llvm-components: riscv
 //@ revisions: riscv64gc_unknown_freebsd
 //@ [riscv64gc_unknown_freebsd] compile-flags: --target riscv64gc-unknown-freebsd
 //@ [riscv64gc_unknown_freebsd] needs-llvm-components: riscv
@@ -525,6 +531,9 @@
 //@ revisions: x86_64_unknown_dragonfly
 //@ [x86_64_unknown_dragonfly] compile-flags: --target x86_64-unknown-dragonfly
 //@ [x86_64_unknown_dragonfly] needs-llvm-components: x86
+//@ revisions: x86_64_unknown_emerald
+//@ [x86_64_unknown_emerald] compile-flags: --target x86_64-unknown-emerald
+//@ [x86_64_unknown_emerald] needs-llvm-components: x86
 //@ revisions: x86_64_unknown_freebsd
 //@ [x86_64_unknown_freebsd] compile-flags: --target x86_64-unknown-freebsd
 //@ [x86_64_unknown_freebsd] needs-llvm-components: x86